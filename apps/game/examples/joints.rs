@@ -1,7 +1,3 @@
-#[path = "../src/car/mod.rs"]
-mod car;
-#[path = "../src/plugins/mod.rs"]
-mod plugins;
 mod src;
 
 use bevy::{diagnostic::FrameTimeDiagnosticsPlugin, prelude::*, window::PresentMode};
@@ -11,8 +7,10 @@ use bevy_rapier3d::{
     render::RapierDebugRenderPlugin,
 };
 
-use car::Configuration;
-use plugins::{CameraType, MainScenePlugin};
+use kazuki::{
+    car::Configuration,
+    plugins::{CameraType, Ground, MainScenePlugin},
+};
 use src::JointsPlugin;
 
 pub fn main() {
@@ -38,6 +36,7 @@ pub fn main() {
         .add_plugins(FrameTimeDiagnosticsPlugin)
         .add_plugins(MainScenePlugin {
             camera_type: CameraType::Fly,
+            ground: Ground::Plane,
         })
         .add_plugins(RapierPhysicsPlugin::<NoUserData>::default())
         .add_plugins(RapierDebugRenderPlugin::default())
@@ -5,15 +5,14 @@ use bevy::{
 use bevy_rapier3d::{geometry::ColliderMassProperties, prelude::*};
 
 use super::JointsPlugin;
-use crate::car::{
-    dynamics::{
-        suspension::{make_front_upright_chasis_joint, make_upright_wheel_joint},
-        UprightJoint, WheelJoint,
+use kazuki::car::Configuration;
+use kazuki::car::{
+    dynamics::suspension::{
+        make_front_upright_chasis_joint, make_upright_wheel_joint, upright_wheel_anchors,
     },
     objects::wheels::get_suspension_geometry,
-    Upright,
+    Corner, Upright, Wheel, WheelAlignment,
 };
-use crate::Configuration;
 
 impl Plugin for JointsPlugin {
     fn build(&self, app: &mut App) {
@@ -36,10 +35,10 @@ fn system_update_physics_active(
     rapier_config.physics_pipeline_active = config.enable_physics;
 }
 
-fn system_steering(config: Res<Configuration>, mut q: Query<(&mut ImpulseJoint, &UprightJoint)>) {
+fn system_steering(config: Res<Configuration>, mut q: Query<(&mut ImpulseJoint, &Upright)>) {
     if config.enable_physics {
         for (mut joint, upright) in q.iter_mut() {
-            if upright.is_front {
+            if upright.corner.is_front() {
                 joint.data.set_motor_position(
                     JointAxis::AngX,
                     config.steering_angle.to_radians(),
@@ -51,14 +50,11 @@ fn system_steering(config: Res<Configuration>, mut q: Query<(&mut ImpulseJoint,
     }
 }
 
-fn system_upright_offset(
-    config: Res<Configuration>,
-    mut q: Query<(&mut ImpulseJoint, &UprightJoint)>,
-) {
+fn system_upright_offset(config: Res<Configuration>, mut q: Query<(&mut ImpulseJoint, &Upright)>) {
     if config.enable_physics {
-        for (mut joint, upright_joint) in q.iter_mut() {
+        for (mut joint, upright) in q.iter_mut() {
             joint.data.set_local_anchor2(Vec3::new(
-                if upright_joint.is_left {
+                if upright.corner.is_left() {
                     config.upright_offset
                 } else {
                     -config.upright_offset
@@ -72,12 +68,12 @@ fn system_upright_offset(
 
 fn system_wheel_offset_and_motor(
     config: Res<Configuration>,
-    mut q: Query<(&mut ImpulseJoint, &WheelJoint)>,
+    mut q: Query<(&mut ImpulseJoint, &Wheel)>,
 ) {
     if config.enable_physics {
-        for (mut joint, wheel_joint) in q.iter_mut() {
+        for (mut joint, wheel) in q.iter_mut() {
             // motor
-            let vel = if wheel_joint.is_left {
+            let vel = if wheel.corner.is_left() {
                 -config.wheel_vel
             } else {
                 config.wheel_vel
@@ -85,17 +81,10 @@ fn system_wheel_offset_and_motor(
             joint.data.set_motor_velocity(JointAxis::AngX, vel, 1.);
 
             // offset
-            let offset = if wheel_joint.is_left {
-                -config.wheel_offset
-            } else {
-                config.wheel_offset
-            };
-            joint
-                .data
-                .set_local_anchor1(Vec3::new(offset * 0.5, 0., 0.));
-            joint
-                .data
-                .set_local_anchor2(Vec3::new(0., config.wheel_offset * -0.5, 0.));
+            let (upright_anchor, wheel_anchor) =
+                upright_wheel_anchors(config.wheel_offset, wheel.corner.is_left());
+            joint.data.set_local_anchor1(upright_anchor);
+            joint.data.set_local_anchor2(wheel_anchor);
         }
     }
 }
@@ -176,6 +165,7 @@ fn spawn_wheel(
     };
     let body_pos_y = 1.6;
     let body_pos = Vec3::new(body_pos_x, body_pos_y, 0.);
+    let corner = Corner::new(true, params.is_left);
     let mut anchor = Vec3::new(params.body_w * 0.5 + params.upright_w * 0.5, 0., 0.);
     if params.is_left {
         anchor.x = -anchor.x;
@@ -193,6 +183,7 @@ fn spawn_wheel(
             config.wheel_offset,
             body_pos,
             anchor,
+            &WheelAlignment::default(),
         );
 
     let body = commands
@@ -221,10 +212,7 @@ fn spawn_wheel(
         .insert(RigidBody::Dynamic)
         .insert(Collider::cuboid(0.2, 0.2, 0.2))
         .insert(ColliderMassProperties::Density(4.))
-        .insert(Upright {
-            is_front: true,
-            is_left: params.is_left,
-        })
+        .insert(Upright { corner, body })
         .id();
 
     let wheel = commands
@@ -243,25 +231,32 @@ fn spawn_wheel(
         .id();
 
     // Wheel - Upright Joint
-    let wheel_joint = make_upright_wheel_joint(config.wheel_offset, params.is_left);
+    let wheel_joint = make_upright_wheel_joint(
+        config.wheel_offset,
+        params.is_left,
+        WheelAlignment::default().wheel_rotation(params.is_left),
+    );
 
     commands.entity(wheel).insert((
         ImpulseJoint::new(upright, wheel_joint),
-        WheelJoint {
-            is_front: true,
-            is_left: params.is_left,
+        Wheel {
+            corner,
+            upright,
+            body,
         },
     ));
 
     // Upright - Body Joint
-    let upright_joint =
-        make_front_upright_chasis_joint(anchor, upright_offset_relative, [-1., 0.], false);
+    let upright_joint = make_front_upright_chasis_joint(
+        anchor,
+        upright_offset_relative,
+        [-1., 0.],
+        false,
+        45.,
+        Quat::IDENTITY,
+    );
 
-    commands.entity(upright).insert((
-        ImpulseJoint::new(body, upright_joint),
-        UprightJoint {
-            is_left: params.is_left,
-            is_front: true,
-        },
-    ));
+    commands
+        .entity(upright)
+        .insert(ImpulseJoint::new(body, upright_joint));
 }
@@ -0,0 +1,47 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::{ExternalForce, ReadMassProperties, Velocity};
+
+use crate::car::{Body, CarSpecs};
+
+/// Drag against the body's velocity and downforce along its down axis, both growing with the
+/// squared speed and split between the front and rear aero centers by the aero balance
+#[allow(clippy::type_complexity)]
+pub fn system_aerodynamics(
+    specs_assets: Res<Assets<CarSpecs>>,
+    mut q_body: Query<
+        (
+            &Handle<CarSpecs>,
+            &Transform,
+            &Velocity,
+            &ReadMassProperties,
+            &mut ExternalForce,
+        ),
+        With<Body>,
+    >,
+) {
+    for (specs_handle, transform, velocity, mass, mut force) in q_body.iter_mut() {
+        let Some(car_specs) = specs_assets.get(specs_handle) else {
+            continue;
+        };
+        let aero = &car_specs.aero;
+        let speed = velocity.linvel.length();
+        if speed == 0. {
+            continue;
+        }
+        let (drag, downforce) = aero.forces(speed);
+        let aero_force =
+            -velocity.linvel / speed * drag + transform.rotation * Vec3::NEG_Y * downforce;
+
+        let center_of_mass = transform.transform_point(mass.local_center_of_mass);
+        for (center, share) in [
+            (aero.front_center, aero.balance),
+            (aero.rear_center, 1. - aero.balance),
+        ] {
+            *force += ExternalForce::at_point(
+                aero_force * share,
+                transform.transform_point(center),
+                center_of_mass,
+            );
+        }
+    }
+}
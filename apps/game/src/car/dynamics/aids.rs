@@ -0,0 +1,89 @@
+use bevy::prelude::*;
+
+use crate::car::{CarSpecs, Corner};
+use crate::plugins::controls::ControlsState;
+
+// Rates at which the ABS eases the brake pedal off a locking wheel and then presses it again, in
+// pedal travel per second
+const ABS_RELEASE_RATE: f32 = 5.;
+const ABS_APPLY_RATE: f32 = 5.;
+
+/// Levels of the driver aids, 0 is off and each level above it uses the matching slip threshold
+/// in `DriverAidsSpecs`
+#[derive(Component)]
+pub struct DriverAids {
+    pub traction_control: usize,
+    pub abs: usize,
+    // Scale of the brake pedal and slip ratio of the last frame on each wheel, indexed by corner
+    abs_scales: [f32; 4],
+    abs_slips: [f32; 4],
+}
+
+impl Default for DriverAids {
+    fn default() -> Self {
+        DriverAids {
+            traction_control: 0,
+            abs: 0,
+            abs_scales: [1.; 4],
+            abs_slips: [0.; 4],
+        }
+    }
+}
+
+impl DriverAids {
+    /// Scale of the engine torque, cut while the driven wheel slipping the most spins faster
+    /// than the traction control allows
+    pub fn traction_control_scale(&self, car_specs: &CarSpecs, slip_ratio: f32) -> f32 {
+        car_specs
+            .driver_aids
+            .traction_control_slip(self.traction_control)
+            .map_or(1., |threshold| (2. - slip_ratio / threshold).clamp(0., 1.))
+    }
+
+    /// Scale of the brake pedal on the wheel at `corner`. While the wheel turns slower than the
+    /// ABS allows the pedal is eased off, then held while the wheel spins back up, and pressed
+    /// again once it turns with the car
+    pub fn abs_scale(
+        &mut self,
+        car_specs: &CarSpecs,
+        corner: Corner,
+        slip_ratio: f32,
+        dt: f32,
+    ) -> f32 {
+        let last_slip = std::mem::replace(&mut self.abs_slips[corner.index()], slip_ratio);
+        let scale = &mut self.abs_scales[corner.index()];
+        *scale = match car_specs.driver_aids.abs_slip(self.abs) {
+            Some(threshold) if -slip_ratio > threshold && slip_ratio <= last_slip => {
+                *scale - ABS_RELEASE_RATE * dt
+            }
+            Some(threshold) if -slip_ratio > threshold => *scale,
+            Some(_) => *scale + ABS_APPLY_RATE * dt,
+            None => 1.,
+        }
+        .clamp(0., 1.);
+        *scale
+    }
+}
+
+/// Cycles through the levels of each aid when requested, going back to off after the last one
+pub fn system_driver_aids(
+    specs_assets: Res<Assets<CarSpecs>>,
+    mut q: Query<(&Handle<CarSpecs>, &ControlsState, &mut DriverAids)>,
+) {
+    for (specs_handle, controls, mut aids) in q.iter_mut() {
+        if !controls.cycle_traction_control && !controls.cycle_abs {
+            continue;
+        }
+        let Some(car_specs) = specs_assets.get(specs_handle) else {
+            continue;
+        };
+        let specs = &car_specs.driver_aids;
+        if controls.cycle_traction_control {
+            aids.traction_control =
+                (aids.traction_control + 1) % (specs.traction_control.len() + 1);
+        }
+        if controls.cycle_abs {
+            aids.abs = (aids.abs + 1) % (specs.abs.len() + 1);
+        }
+    }
+}
@@ -0,0 +1,50 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::{ImpulseJoint, JointAxis, MotorModel, RapierContext};
+
+use crate::car::{
+    dynamics::{aids::DriverAids, telemetry::WheelTelemetry},
+    CarSpecs, Wheel,
+};
+use crate::plugins::controls::ControlsState;
+
+// Damping of the brake motor, high enough to hold the wheel still when the torque allows it
+const BRAKE_DAMPING: f32 = 1e4;
+
+/// Brakes are modeled as the wheel joint motor driving the wheel speed to zero, with the brake
+/// torque as the maximum force the motor can apply. The ABS eases the pedal off wheels that lock
+/// up, but not the handbrake.
+pub fn system_brakes(
+    rapier_context: Res<RapierContext>,
+    specs_assets: Res<Assets<CarSpecs>>,
+    mut q_body: Query<(Entity, &Handle<CarSpecs>, &ControlsState, &mut DriverAids)>,
+    mut q: Query<(&mut ImpulseJoint, &Wheel, &WheelTelemetry)>,
+) {
+    for (body_entity, specs_handle, controls, mut aids) in q_body.iter_mut() {
+        let Some(car_specs) = specs_assets.get(specs_handle) else {
+            continue;
+        };
+        for (mut joint, wheel, telemetry) in q.iter_mut() {
+            if wheel.body != body_entity {
+                continue;
+            }
+            let abs_scale = aids.abs_scale(
+                car_specs,
+                wheel.corner,
+                telemetry.slip_ratio,
+                rapier_context.integration_parameters.dt,
+            );
+            let torque = car_specs.brakes.wheel_torque(
+                wheel.corner.is_front(),
+                controls.brake * abs_scale,
+                controls.handbrake,
+            );
+            // With no torque the max force releases the wheel, a motor without stiffness and
+            // damping would lock it instead
+            joint
+                .data
+                .set_motor_model(JointAxis::AngX, MotorModel::ForceBased)
+                .set_motor(JointAxis::AngX, 0., 0., 0., BRAKE_DAMPING)
+                .set_motor_max_force(JointAxis::AngX, torque);
+        }
+    }
+}
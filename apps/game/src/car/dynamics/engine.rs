@@ -0,0 +1,159 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::{ColliderMassProperties, ExternalForce, RapierContext};
+use std::f32::consts::PI;
+
+use crate::car::{
+    dynamics::{
+        aids::DriverAids, fuel::FuelTank, telemetry::WheelTelemetry, wheel_spin_axis, Drivetrain,
+    },
+    objects::wheels::wheel_mass_properties,
+    Body, CarSpecs, Corner, Wheel,
+};
+use crate::plugins::controls::ControlsState;
+
+const RAD_PER_SEC_TO_RPM: f32 = 60. / (2. * PI);
+
+pub fn system_gearbox(
+    specs_assets: Res<Assets<CarSpecs>>,
+    mut q: Query<(&Handle<CarSpecs>, &ControlsState, &mut Drivetrain)>,
+) {
+    for (specs_handle, controls, mut drivetrain) in q.iter_mut() {
+        if controls.shift == 0 {
+            continue;
+        }
+        let Some(car_specs) = specs_assets.get(specs_handle) else {
+            continue;
+        };
+        drivetrain.gear =
+            (drivetrain.gear + controls.shift).clamp(-1, car_specs.gearbox.top_gear());
+    }
+}
+
+/// The engine turns with the driven wheels, so they carry its inertia through the current gear
+pub fn system_driveline_inertia(
+    specs_assets: Res<Assets<CarSpecs>>,
+    q_body: Query<(Entity, &Handle<CarSpecs>, &Drivetrain), With<Body>>,
+    mut q_wheels: Query<(&Wheel, &mut ColliderMassProperties)>,
+) {
+    for (body_entity, specs_handle, drivetrain) in q_body.iter() {
+        let Some(car_specs) = specs_assets.get(specs_handle) else {
+            continue;
+        };
+        for (wheel, mut mass_properties) in q_wheels.iter_mut() {
+            if wheel.body == body_entity {
+                mass_properties.set_if_neq(wheel_mass_properties(
+                    car_specs,
+                    wheel.corner.is_front(),
+                    drivetrain.gear,
+                ));
+            }
+        }
+    }
+}
+
+/// Engine rpm follows the driven wheels through the gearbox, and the torque from the curve is
+/// split between the axles and then between each axle's wheels by the differentials, with the
+/// reaction on the body. Traction control cuts the torque while the driven wheels spin, and the
+/// engine burns fuel for its work until the tank runs dry.
+#[allow(clippy::type_complexity)]
+pub fn system_engine(
+    rapier_context: Res<RapierContext>,
+    specs_assets: Res<Assets<CarSpecs>>,
+    mut q_body: Query<
+        (
+            Entity,
+            &Handle<CarSpecs>,
+            &ControlsState,
+            &mut Drivetrain,
+            &DriverAids,
+            &mut FuelTank,
+            &mut ExternalForce,
+        ),
+        (With<Body>, Without<Wheel>),
+    >,
+    mut q_wheels: Query<(&Wheel, &Transform, &WheelTelemetry, &mut ExternalForce)>,
+) {
+    for (body_entity, specs_handle, controls, mut drivetrain, aids, mut tank, mut body_force) in
+        q_body.iter_mut()
+    {
+        let Some(car_specs) = specs_assets.get(specs_handle) else {
+            continue;
+        };
+        let (front_split, rear_split) = car_specs.drivetrain.layout.axle_split();
+        let axle_split = |is_front: bool| if is_front { front_split } else { rear_split };
+        let is_driven = |is_front: bool| axle_split(is_front) > 0.;
+
+        let mut wheel_spins = [0.; 4];
+        let mut driven_spin = 0.;
+        let mut driven_wheels = 0;
+        let mut driven_slip = 0_f32;
+        for (wheel, _, telemetry, _) in q_wheels.iter() {
+            if wheel.body != body_entity {
+                continue;
+            }
+            let spin = telemetry.angular_velocity;
+            wheel_spins[wheel.corner.index()] = spin;
+            if is_driven(wheel.corner.is_front()) {
+                driven_spin += spin;
+                driven_wheels += 1;
+                driven_slip = driven_slip.max(telemetry.slip_ratio);
+            }
+        }
+        if driven_wheels == 0 {
+            continue;
+        }
+        driven_spin /= driven_wheels as f32;
+
+        let engine = &car_specs.engine;
+        let ratio = car_specs.gearbox.ratio(drivetrain.gear);
+        drivetrain.rpm = if ratio == 0. {
+            // Free revving in neutral
+            engine.idle_rpm + controls.accelerator * (engine.rev_limit - engine.idle_rpm)
+        } else {
+            (driven_spin * ratio * RAD_PER_SEC_TO_RPM).max(engine.idle_rpm)
+        };
+        let engine_torque = if drivetrain.rpm < engine.rev_limit && !tank.is_empty() {
+            engine.torque(drivetrain.rpm)
+                * controls.accelerator
+                * aids.traction_control_scale(car_specs, driven_slip)
+        } else {
+            0.
+        };
+        let dt = rapier_context.integration_parameters.dt;
+        tank.burn(
+            &car_specs.fuel,
+            engine_torque * drivetrain.rpm / RAD_PER_SEC_TO_RPM * dt,
+        );
+
+        // Torque on each wheel, indexed by corner
+        let mut wheel_torques = [0.; 4];
+        for (is_front, axle_split, differential) in [
+            (true, front_split, &car_specs.drivetrain.front_differential),
+            (false, rear_split, &car_specs.drivetrain.rear_differential),
+        ] {
+            let left = Corner::new(is_front, true).index();
+            let right = Corner::new(is_front, false).index();
+            let equalizing_torque = if dt > 0. {
+                0.5 * car_specs.wheel_inertia_in_gear(is_front, drivetrain.gear)
+                    * (wheel_spins[left] - wheel_spins[right])
+                    / dt
+            } else {
+                0.
+            };
+            let (left_torque, right_torque) =
+                differential.split(engine_torque * ratio * axle_split, equalizing_torque);
+            wheel_torques[left] = left_torque;
+            wheel_torques[right] = right_torque;
+        }
+
+        for (wheel, transform, _, mut force) in q_wheels.iter_mut() {
+            if wheel.body != body_entity || !is_driven(wheel.corner.is_front()) {
+                continue;
+            }
+            let torque = wheel_spin_axis(transform.rotation, wheel.corner.is_left())
+                * wheel_torques[wheel.corner.index()];
+            force.torque += torque;
+            body_force.torque -= torque;
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::{ColliderMassProperties, MassProperties};
+
+use crate::car::{Body, CarSpecs, FuelSpecs};
+
+// The body mass properties are rebuilt each time the fuel mass crosses a step of this many kg,
+// rather than on every frame the engine burns fuel
+const FUEL_MASS_STEP: f32 = 0.5;
+
+/// Fuel left in the car's tank, in liters
+#[derive(Component)]
+pub struct FuelTank {
+    pub fuel: f32,
+}
+
+impl FuelTank {
+    pub fn new(specs: &FuelSpecs) -> Self {
+        FuelTank {
+            fuel: specs.start_load.clamp(0., specs.capacity),
+        }
+    }
+
+    /// Burns the fuel for `work` J of engine work, until the tank runs dry
+    pub fn burn(&mut self, specs: &FuelSpecs, work: f32) {
+        self.fuel = (self.fuel - specs.consumption * work.max(0.) * 1e-6).max(0.);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.fuel <= 0.
+    }
+
+    /// Mass of the fuel carried by the body, rounded up to the next `FUEL_MASS_STEP`
+    pub fn mass(&self, specs: &FuelSpecs) -> f32 {
+        (specs.mass(self.fuel) / FUEL_MASS_STEP).ceil() * FUEL_MASS_STEP
+    }
+}
+
+/// Mass properties of the body carrying `fuel_mass` kg in its tank. The tank sits at the center
+/// of mass, so the fuel adds to the mass but not to the inertia
+pub fn body_mass_properties(car_specs: &CarSpecs, fuel_mass: f32) -> ColliderMassProperties {
+    ColliderMassProperties::MassProperties(MassProperties {
+        local_center_of_mass: car_specs.center_of_mass,
+        mass: car_specs.body_mass() + fuel_mass,
+        principal_inertia: car_specs.body_inertia,
+        ..default()
+    })
+}
+
+/// The body gets lighter as the engine burns its fuel
+pub fn system_fuel_mass(
+    specs_assets: Res<Assets<CarSpecs>>,
+    mut q: Query<(&Handle<CarSpecs>, &FuelTank, &mut ColliderMassProperties), With<Body>>,
+) {
+    for (specs_handle, tank, mut mass_properties) in q.iter_mut() {
+        let Some(car_specs) = specs_assets.get(specs_handle) else {
+            continue;
+        };
+        mass_properties.set_if_neq(body_mass_properties(car_specs, tank.mass(&car_specs.fuel)));
+    }
+}
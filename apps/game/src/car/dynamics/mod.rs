@@ -1,16 +1,50 @@
 use bevy::prelude::*;
+use bevy_rapier3d::prelude::ExternalForce;
 
+pub mod aero;
+pub mod aids;
+pub mod brakes;
+pub mod engine;
+pub mod fuel;
+pub mod surface;
 pub mod suspension;
+pub mod telemetry;
+pub mod tires;
 
-#[derive(Component)]
-pub struct WheelJoint {
-    pub is_left: bool,
-    pub is_front: bool,
+/// Systems that add to the `ExternalForce` of the car parts run in `Forces`, after the forces of
+/// the previous frame are cleared in `ResetForces`
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum DynamicsSet {
+    ResetForces,
+    Forces,
 }
 
 #[derive(Component)]
-pub struct UprightJoint {
-    pub is_left: bool,
-    pub is_front: bool,
+pub struct Drivetrain {
+    pub rpm: f32,
+    // -1 is reverse, 0 is neutral
+    pub gear: i8,
+}
+
+impl Default for Drivetrain {
+    fn default() -> Self {
+        Drivetrain { rpm: 0., gear: 1 }
+    }
 }
 
+/// World axis around which a positive wheel spin rolls the car forward
+pub fn wheel_spin_axis(wheel_rotation: Quat, is_left: bool) -> Vec3 {
+    // The wheel joint rotates around the wheel's -Y axis, which points outwards on the left side
+    let axle = wheel_rotation * Vec3::NEG_Y;
+    if is_left {
+        -axle
+    } else {
+        axle
+    }
+}
+
+pub fn system_reset_forces(mut q: Query<&mut ExternalForce>) {
+    for mut force in q.iter_mut() {
+        *force = ExternalForce::default();
+    }
+}
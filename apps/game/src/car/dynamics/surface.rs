@@ -0,0 +1,78 @@
+use bevy::prelude::*;
+use serde::Deserialize;
+
+// Distance between the peaks of the bump noise, in meters
+const BUMP_WAVELENGTH: f32 = 0.5;
+// Speed in m/s at which the bumps are felt in full, they fade out as the car stops
+const BUMP_SPEED: f32 = 10.;
+
+/// Material of a surface collider, looked up by the tire model under each wheel. Colliders
+/// without one are treated as asphalt
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq, Deserialize)]
+pub enum SurfaceMaterial {
+    #[default]
+    Asphalt,
+    Kerb,
+    Grass,
+    Gravel,
+    WetAsphalt,
+}
+
+pub struct SurfaceProperties {
+    // Scale of the tire friction coefficients
+    pub grip: f32,
+    // Rolling resistance force per N of normal load
+    pub rolling_resistance: f32,
+    // Amplitude of the vertical force from the bumps of the surface, per N of normal load
+    pub bump_noise: f32,
+}
+
+impl SurfaceMaterial {
+    pub fn properties(&self) -> SurfaceProperties {
+        let (grip, rolling_resistance, bump_noise) = match self {
+            SurfaceMaterial::Asphalt => (1., 0.015, 0.),
+            SurfaceMaterial::Kerb => (0.9, 0.02, 0.3),
+            SurfaceMaterial::Grass => (0.55, 0.06, 0.1),
+            SurfaceMaterial::Gravel => (0.5, 0.15, 0.2),
+            SurfaceMaterial::WetAsphalt => (0.7, 0.015, 0.),
+        };
+        SurfaceProperties {
+            grip,
+            rolling_resistance,
+            bump_noise,
+        }
+    }
+}
+
+impl SurfaceProperties {
+    /// Vertical force of the bumps under a wheel at `point` carrying `load`, moving at `speed`.
+    /// The bumps are fixed on the surface, so driving over the same spot feels the same
+    pub fn bump_force(&self, point: Vec3, load: f32, speed: f32) -> f32 {
+        if self.bump_noise == 0. {
+            return 0.;
+        }
+        let strength = (speed / BUMP_SPEED).min(1.);
+        self.bump_noise * load * strength * value_noise(point.xz() / BUMP_WAVELENGTH)
+    }
+}
+
+/// Smooth noise between -1 and 1, interpolating random values at the integer coordinates
+fn value_noise(position: Vec2) -> f32 {
+    let cell = position.floor();
+    let t = position - cell;
+    let t = t * t * (3. - 2. * t);
+    let (x, y) = (cell.x as i32, cell.y as i32);
+    let bottom = lattice_value(x, y) + (lattice_value(x + 1, y) - lattice_value(x, y)) * t.x;
+    let top =
+        lattice_value(x, y + 1) + (lattice_value(x + 1, y + 1) - lattice_value(x, y + 1)) * t.x;
+    bottom + (top - bottom) * t.y
+}
+
+/// Random value between -1 and 1 for an integer coordinate, from an integer hash
+fn lattice_value(x: i32, y: i32) -> f32 {
+    let mut hash = (x as u32).wrapping_mul(0x8da6_b343) ^ (y as u32).wrapping_mul(0xd816_3841);
+    hash ^= hash >> 13;
+    hash = hash.wrapping_mul(0x5bd1_e995);
+    hash ^= hash >> 15;
+    (hash as f32 / u32::MAX as f32) * 2. - 1.
+}
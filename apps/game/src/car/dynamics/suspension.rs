@@ -1,37 +1,13 @@
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::{
-    GenericJoint, GenericJointBuilder, ImpulseJoint, JointAxesMask, JointAxis,
+    GenericJoint, GenericJointBuilder, ImpulseJoint, JointAxesMask, JointAxis, MotorModel,
+    ReadMassProperties, Velocity,
 };
-use std::f32::consts::PI;
+use std::f32::consts::FRAC_1_SQRT_2;
 
-use crate::car::{
-    dynamics::{UprightJoint, WheelJoint},
-    RearWheel,
-};
+use crate::car::{Body, CarSpecs, SuspensionSpecs, Upright};
 use crate::plugins::controls::ControlsState;
 
-fn steering_to_angle(steering_wheel_degrees: f32) -> f32 {
-    let turning_degrees = 90.;
-    let steering_wheel_degrees_range = 900.;
-    turning_degrees * (steering_wheel_degrees + steering_wheel_degrees_range * 0.5)
-        / steering_wheel_degrees_range
-        - turning_degrees * 0.5
-}
-
-pub fn system_rear_axle_motor(
-    controls: Res<ControlsState>,
-    mut q: Query<(&mut ImpulseJoint, &WheelJoint), With<RearWheel>>,
-) {
-    for (mut joint, wheel_joint) in q.iter_mut() {
-        let vel = if wheel_joint.is_left {
-            controls.accelerator * -1000.
-        } else {
-            controls.accelerator * 1000.
-        };
-        joint.data.set_motor_velocity(JointAxis::AngX, vel, 1.);
-    }
-}
-
 // pub fn system_update_upright_steering(
 //     controls: Res<ControlsState>,
 //     mut q: Query<(&mut Transform, &Upright)>,
@@ -44,26 +20,155 @@ pub fn system_rear_axle_motor(
 //     }
 // }
 pub fn system_update_upright_steering(
-    controls: Res<ControlsState>,
-    mut q: Query<(&mut ImpulseJoint, &UprightJoint)>,
+    specs_assets: Res<Assets<CarSpecs>>,
+    q_body: Query<(&Handle<CarSpecs>, &ControlsState), With<Body>>,
+    mut q: Query<(&mut ImpulseJoint, &Upright)>,
 ) {
-    let angle = steering_to_angle(controls.steering_wheel_degrees);
-    for (mut joint, upright_joint) in q.iter_mut() {
-        if upright_joint.is_front {
+    for (mut joint, upright) in q.iter_mut() {
+        if !upright.corner.is_front() {
+            continue;
+        }
+        let Ok((specs_handle, controls)) = q_body.get(upright.body) else {
+            continue;
+        };
+        let Some(car_specs) = specs_assets.get(specs_handle) else {
+            continue;
+        };
+        let angle = car_specs.steering.wheel_angle(
+            controls.steering_wheel_degrees,
+            upright.corner.is_left(),
+            car_specs.wheelbase(),
+            car_specs.front_track(),
+        );
+        joint
+            .data
+            .set_motor_position(JointAxis::AngX, -angle.to_radians(), 1e6, 1e5);
+    }
+}
+
+/// Target position, stiffness and damping of the vertical joint motor that models the spring,
+/// bump stop, damper and anti-roll bar of one corner.
+///
+/// `travel` is the joint position along its X axis: 0 at full bump, `-specs.travel` at full
+/// droop. `rate` is positive when the suspension is compressing. The anti-roll bar pulls the
+/// corner towards `opposite_travel`, the travel of the other corner on the same axle.
+fn spring_damper_motor(
+    specs: &SuspensionSpecs,
+    travel: f32,
+    rate: f32,
+    anti_roll_rate: f32,
+    opposite_travel: f32,
+) -> (f32, f32, f32) {
+    // The spring pushes the upright down with `preload` at full droop, so its rest position is
+    // below the droop limit
+    let spring_target = -specs.travel - specs.preload / specs.spring_rate;
+    let bump_stop_start = -specs.bump_stop_travel;
+    let (target, stiffness) = if travel > bump_stop_start {
+        let stiffness = specs.spring_rate + specs.bump_stop_rate;
+        let target = (specs.spring_rate * spring_target + specs.bump_stop_rate * bump_stop_start)
+            / stiffness;
+        (target, stiffness)
+    } else {
+        (spring_target, specs.spring_rate)
+    };
+    let target =
+        (stiffness * target + anti_roll_rate * opposite_travel) / (stiffness + anti_roll_rate);
+    let stiffness = stiffness + anti_roll_rate;
+    let damping = if rate > 0. {
+        specs.bump_damping
+    } else {
+        specs.rebound_damping
+    };
+    (target, stiffness, damping)
+}
+
+/// Position of an upright along the axis of its joint to the body: 0 at full bump,
+/// `-SuspensionSpecs::travel` at full droop
+pub fn suspension_travel(
+    joint: &ImpulseJoint,
+    body_transform: &Transform,
+    upright_transform: &Transform,
+) -> f32 {
+    let up = body_transform.rotation * joint.data.local_axis1();
+    let anchor = body_transform.transform_point(joint.data.local_anchor1());
+    (upright_transform.transform_point(joint.data.local_anchor2()) - anchor).dot(up)
+}
+
+#[allow(clippy::type_complexity)]
+pub fn system_suspension_spring_damper(
+    specs_assets: Res<Assets<CarSpecs>>,
+    q_body: Query<
+        (
+            Entity,
+            &Handle<CarSpecs>,
+            &Transform,
+            &Velocity,
+            &ReadMassProperties,
+        ),
+        With<Body>,
+    >,
+    mut q: Query<(&mut ImpulseJoint, &Upright, &Transform, &Velocity)>,
+) {
+    for (body_entity, specs_handle, body_transform, body_velocity, body_mass) in q_body.iter() {
+        let Some(car_specs) = specs_assets.get(specs_handle) else {
+            continue;
+        };
+        let body_com = body_transform.transform_point(body_mass.local_center_of_mass);
+
+        // Travel and rate of each corner, indexed by corner
+        let mut corners = [(0., 0.); 4];
+        for (joint, upright, upright_transform, upright_velocity) in q.iter() {
+            if upright.body != body_entity {
+                continue;
+            }
+            let up = body_transform.rotation * joint.data.local_axis1();
+            let anchor = body_transform.transform_point(joint.data.local_anchor1());
+
+            let travel = suspension_travel(joint, body_transform, upright_transform);
+            let rate = (upright_velocity.linvel
+                - body_velocity.linear_velocity_at_point(anchor, body_com))
+            .dot(up);
+            corners[upright.corner.index()] = (travel, rate);
+        }
+
+        for (mut joint, upright, _, _) in q.iter_mut() {
+            if upright.body != body_entity {
+                continue;
+            }
+            let corner = upright.corner;
+            let (travel, rate) = corners[corner.index()];
+            let (opposite_travel, _) = corners[corner.opposite().index()];
+            let (target, stiffness, damping) = spring_damper_motor(
+                car_specs.suspension(corner),
+                travel,
+                rate,
+                car_specs.anti_roll_bars.rate(corner.is_front()),
+                opposite_travel,
+            );
             joint
                 .data
-                .set_motor_position(JointAxis::AngX, -angle.to_radians(), 1e6, 1e5);
+                .set_motor(JointAxis::X, target, 0., stiffness, damping);
         }
     }
 }
 
+// Joint frame with its X axis along the local -Y axis of the wheel, its axle
+const WHEEL_AXLE_FRAME: Quat = Quat::from_xyzw(0., 0., -FRAC_1_SQRT_2, FRAC_1_SQRT_2);
+// Joint frame with its X axis along the local Y axis of the upright, its steering axis
+const STEERING_AXIS_FRAME: Quat = Quat::from_xyzw(0., 0., FRAC_1_SQRT_2, FRAC_1_SQRT_2);
+
+/// `upright_rotation` is the rotation of the upright relative to the body, its Y axis is the
+/// steering axis along which the suspension travels. Unless `lock_direction`, the upright steers
+/// up to `steering_lock` degrees either way
 pub fn make_front_upright_chasis_joint(
     anchor: Vec3,
     offset: f32,
     suspension_limits: [f32; 2],
     lock_direction: bool,
+    steering_lock: f32,
+    upright_rotation: Quat,
 ) -> GenericJoint {
-    // X is the vertical axis
+    // X is the steering axis, along which the suspension travels
     // ANG_X is the rotation axis
     let locked_axes = if lock_direction {
         JointAxesMask::Y
@@ -75,15 +180,19 @@ pub fn make_front_upright_chasis_joint(
         JointAxesMask::Y | JointAxesMask::Z | JointAxesMask::ANG_Y | JointAxesMask::ANG_Z
     };
     let mut builder = GenericJointBuilder::new(locked_axes)
-        .local_axis2(Vec3::Y)
-        .local_axis1(Vec3::Y)
+        .local_basis2(STEERING_AXIS_FRAME)
+        .local_basis1(upright_rotation * STEERING_AXIS_FRAME)
         .local_anchor2(Vec3::new(-offset, 0., 0.))
         .local_anchor1(anchor)
-        .limits(JointAxis::X, suspension_limits);
+        .limits(JointAxis::X, suspension_limits)
+        .motor_model(JointAxis::X, MotorModel::ForceBased);
     if lock_direction {
     } else {
         // builder = builder.limits(JointAxis::X, suspension_limits);
-        builder = builder.limits(JointAxis::AngX, [-45_f32.to_radians(), 45_f32.to_radians()]);
+        builder = builder.limits(
+            JointAxis::AngX,
+            [-steering_lock.to_radians(), steering_lock.to_radians()],
+        );
         builder = builder.set_motor(JointAxis::AngX, 0., 0., 1e6, 1e5);
     }
     let mut joint = builder.build();
@@ -91,8 +200,23 @@ pub fn make_front_upright_chasis_joint(
     joint
 }
 
-pub fn make_upright_wheel_joint(abs_offset: f32, is_left: bool) -> GenericJoint {
-    let offset = is_left.then(|| -abs_offset).unwrap_or(abs_offset);
+/// Anchors of the wheel joint on the upright and on the wheel, half the wheel offset away from
+/// each of them
+pub fn upright_wheel_anchors(abs_offset: f32, is_left: bool) -> (Vec3, Vec3) {
+    let offset = if is_left { -abs_offset } else { abs_offset };
+    (
+        Vec3::new(offset * 0.5, 0., 0.),
+        Vec3::new(0., abs_offset * -0.5, 0.),
+    )
+}
+
+/// `wheel_rotation` is the rotation of the wheel relative to the upright, with its camber and toe
+pub fn make_upright_wheel_joint(
+    abs_offset: f32,
+    is_left: bool,
+    wheel_rotation: Quat,
+) -> GenericJoint {
+    let (upright_anchor, wheel_anchor) = upright_wheel_anchors(abs_offset, is_left);
     let builder = GenericJointBuilder::new(
         JointAxesMask::X
             | JointAxesMask::Y
@@ -100,14 +224,10 @@ pub fn make_upright_wheel_joint(abs_offset: f32, is_left: bool) -> GenericJoint
             | JointAxesMask::ANG_Y
             | JointAxesMask::ANG_Z,
     )
-    .local_axis1(if is_left { -Vec3::X } else { Vec3::X })
-    .local_axis2(-Vec3::Y)
-    .local_basis1(Quat::from_axis_angle(
-        Vec3::Y,
-        if is_left { 0. } else { PI },
-    )) // hackfix
-    .local_anchor1(Vec3::new(offset * 0.5, 0., 0.))
-    .local_anchor2(Vec3::new(0., abs_offset * -0.5, 0.));
+    .local_basis2(WHEEL_AXLE_FRAME)
+    .local_basis1(wheel_rotation * WHEEL_AXLE_FRAME)
+    .local_anchor1(upright_anchor)
+    .local_anchor2(wheel_anchor);
     let mut joint = builder.build();
     joint.set_contacts_enabled(false);
     joint
@@ -0,0 +1,53 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::{ImpulseJoint, Velocity};
+
+use crate::car::{
+    dynamics::{suspension::suspension_travel, wheel_spin_axis},
+    Body, CarSpecs, Upright, Wheel,
+};
+
+/// Sensor readings of a wheel, shared by the aids, HUD and logging. The joint readings are taken
+/// before the forces of each step by `system_wheel_telemetry`, the contact readings by
+/// `system_tire_forces` from the slips it computes
+#[derive(Component, Clone, Copy, Default)]
+pub struct WheelTelemetry {
+    // Spin of the wheel relative to its upright in rad/s, positive rolls the car forward
+    pub angular_velocity: f32,
+    // Speed of the wheel over the ground along its heading, in m/s
+    pub contact_speed: f32,
+    pub slip_ratio: f32,
+    // Radians, positive when the wheel slides towards its left
+    pub slip_angle: f32,
+    // Compression of the suspension from full droop, in meters
+    pub suspension_deflection: f32,
+    // Normal load on the tire in N, 0 in the air
+    pub vertical_load: f32,
+}
+
+pub fn system_wheel_telemetry(
+    specs_assets: Res<Assets<CarSpecs>>,
+    mut q_wheels: Query<(
+        &Handle<CarSpecs>,
+        &Wheel,
+        &Transform,
+        &Velocity,
+        &mut WheelTelemetry,
+    )>,
+    q_uprights: Query<(&ImpulseJoint, &Transform, &Velocity), With<Upright>>,
+    q_body: Query<&Transform, With<Body>>,
+) {
+    for (specs_handle, wheel, transform, velocity, mut telemetry) in q_wheels.iter_mut() {
+        let Ok((upright_joint, upright_transform, upright_velocity)) =
+            q_uprights.get(wheel.upright)
+        else {
+            continue;
+        };
+        telemetry.angular_velocity = (velocity.angvel - upright_velocity.angvel)
+            .dot(wheel_spin_axis(transform.rotation, wheel.corner.is_left()));
+        let specs = specs_assets.get(specs_handle);
+        if let (Ok(body_transform), Some(car_specs)) = (q_body.get(wheel.body), specs) {
+            let travel = suspension_travel(upright_joint, body_transform, upright_transform);
+            telemetry.suspension_deflection = travel + car_specs.suspension(wheel.corner).travel;
+        }
+    }
+}
@@ -0,0 +1,313 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::{
+    ExternalForce, ImpulseJoint, JointAxis, RapierConfiguration, RapierContext, ReadMassProperties,
+    TimestepMode, Velocity,
+};
+
+use crate::car::{
+    dynamics::{surface::SurfaceMaterial, telemetry::WheelTelemetry, wheel_spin_axis},
+    CarSpecs, TireSpecs, Wheel,
+};
+
+// Below this speed the slips are computed as if the wheel moved at it, otherwise they blow up
+// when the car stops
+const MIN_SLIP_SPEED: f32 = 1.;
+const GRAVITY: f32 = 9.81;
+// Fraction of the slip speeds the tire forces may cancel within a frame. The corners share the
+// body, so each of them moves it further than its own load suggests
+const SLIP_CANCEL_FRACTION: f32 = 0.5;
+// Same for a wheel held by its brake, which pushes on the whole car through the body and pitches
+// it too, so the car stopped on its brakes would rock back and forth and creep
+const HELD_SLIP_CANCEL_FRACTION: f32 = 0.2;
+
+/// Air and track temperatures in °C, which the tires heat up from or cool down to
+#[derive(Resource, Clone, Copy)]
+pub struct AmbientConditions {
+    pub air_temperature: f32,
+    pub track_temperature: f32,
+}
+
+impl Default for AmbientConditions {
+    fn default() -> Self {
+        AmbientConditions {
+            air_temperature: 20.,
+            track_temperature: 30.,
+        }
+    }
+}
+
+/// Temperatures, pressure and wear of the tire of a wheel
+#[derive(Component, Clone, Copy)]
+pub struct TireState {
+    // °C, the surface heats up from sliding and passes its heat on to the core
+    pub surface_temperature: f32,
+    pub core_temperature: f32,
+    // Gauge pressure in kPa, following the core temperature
+    pub pressure: f32,
+    // Fraction of the tread worn, 0 for a new tire and 1 for a fully worn one
+    pub wear: f32,
+}
+
+impl TireState {
+    /// A new tire at the temperature it was inflated at
+    pub fn new(specs: &TireSpecs) -> Self {
+        TireState {
+            surface_temperature: specs.inflation_temperature,
+            core_temperature: specs.inflation_temperature,
+            pressure: specs.cold_pressure,
+            wear: 0.,
+        }
+    }
+
+    /// Scale of the tire's peak friction coefficients
+    pub fn grip(&self, specs: &TireSpecs) -> f32 {
+        specs.condition_grip(self.surface_temperature, self.pressure, self.wear)
+    }
+
+    /// Advances the tire by `dt` while it slides dissipating `slip_power` in W, moving at `speed`
+    fn update(
+        &mut self,
+        specs: &TireSpecs,
+        ambient: &AmbientConditions,
+        slip_power: f32,
+        speed: f32,
+        on_track: bool,
+        dt: f32,
+    ) {
+        let thermal = &specs.thermal;
+        let to_core = thermal.surface_to_core * (self.surface_temperature - self.core_temperature);
+        let to_air = (thermal.surface_to_air + thermal.speed_cooling * speed)
+            * (self.surface_temperature - ambient.air_temperature);
+        let to_track = if on_track {
+            thermal.surface_to_track * (self.surface_temperature - ambient.track_temperature)
+        } else {
+            0.
+        };
+        let surface_heat = thermal.slip_heating * slip_power - to_core - to_air - to_track;
+        let core_heat =
+            to_core - thermal.core_to_air * (self.core_temperature - ambient.air_temperature);
+
+        self.surface_temperature += surface_heat / thermal.surface_heat_capacity * dt;
+        self.core_temperature += core_heat / thermal.core_heat_capacity * dt;
+        self.pressure = specs.pressure(self.core_temperature);
+        self.wear = (self.wear + specs.wear_rate * slip_power * dt * 1e-6).min(1.);
+    }
+}
+
+struct WheelContact {
+    point: Vec3,
+    // Ground normal, pointing out of the ground
+    normal: Vec3,
+    // Normal load in N
+    load: f32,
+    // Collider carrying most of the load
+    surface: Entity,
+}
+
+/// Contact of a wheel touching the ground, from the contact impulses of the last physics substep
+fn wheel_contact(
+    rapier_context: &RapierContext,
+    wheel: Entity,
+    substep_dt: f32,
+) -> Option<WheelContact> {
+    let mut point = Vec3::ZERO;
+    let mut points = 0;
+    let mut normal = Vec3::ZERO;
+    let mut impulse = 0.;
+    let mut surface = None;
+    let mut surface_impulse = 0.;
+    for pair in rapier_context.contacts_with(wheel) {
+        if !pair.has_any_active_contacts() {
+            continue;
+        }
+        // Manifold normals point from the first collider to the second one
+        let (sign, other) = if pair.collider1() == wheel {
+            (-1., pair.collider2())
+        } else {
+            (1., pair.collider1())
+        };
+        let mut pair_impulse = 0.;
+        for manifold in pair.manifolds() {
+            for contact in manifold.solver_contacts() {
+                point += contact.point();
+                points += 1;
+            }
+            let manifold_impulse = manifold
+                .points()
+                .map(|contact| contact.impulse())
+                .sum::<f32>();
+            normal += manifold.normal() * sign * manifold_impulse;
+            pair_impulse += manifold_impulse;
+        }
+        impulse += pair_impulse;
+        if surface.is_none() || pair_impulse > surface_impulse {
+            surface = Some(other);
+            surface_impulse = pair_impulse;
+        }
+    }
+    if points == 0 || impulse <= 0. {
+        return None;
+    }
+    Some(WheelContact {
+        point: point / points as f32,
+        normal: normal.normalize(),
+        load: impulse / substep_dt,
+        surface: surface?,
+    })
+}
+
+/// Clamps `force` between zero and `limit`
+fn clamp_towards_zero(force: f32, limit: f32) -> f32 {
+    force.clamp(limit.min(0.), limit.max(0.))
+}
+
+/// Longitudinal and lateral tire forces from the Magic Formula, applied at the contact patch of
+/// each wheel touching the ground. The wheel colliders have no friction, they only carry the
+/// normal load.
+///
+/// The forces are applied for a whole frame, so they are capped to what would bring the slip
+/// speeds to zero within it, which a stiff tire would otherwise overshoot. Runs after the engine
+/// to account for its torque.
+///
+/// The slip power of each tire heats and wears it, which changes its grip. The material of the
+/// surface under each wheel scales the grip and adds rolling resistance and bumps.
+///
+/// The contact speed, slips and load of each wheel go to its `WheelTelemetry`.
+#[allow(clippy::type_complexity)]
+pub fn system_tire_forces(
+    ambient: Res<AmbientConditions>,
+    specs_assets: Res<Assets<CarSpecs>>,
+    rapier_config: Res<RapierConfiguration>,
+    rapier_context: Res<RapierContext>,
+    mut q_wheels: Query<(
+        Entity,
+        &Handle<CarSpecs>,
+        &Wheel,
+        &ImpulseJoint,
+        &Transform,
+        &Velocity,
+        &ReadMassProperties,
+        &mut ExternalForce,
+        &mut TireState,
+        &mut WheelTelemetry,
+    )>,
+    q_surfaces: Query<&SurfaceMaterial>,
+) {
+    let dt = rapier_context.integration_parameters.dt;
+    if dt <= 0. {
+        return;
+    }
+    let substeps = match rapier_config.timestep_mode {
+        TimestepMode::Fixed { substeps, .. }
+        | TimestepMode::Variable { substeps, .. }
+        | TimestepMode::Interpolated { substeps, .. } => substeps,
+    };
+    for (
+        entity,
+        specs_handle,
+        wheel,
+        joint,
+        transform,
+        velocity,
+        mass,
+        mut force,
+        mut tire,
+        mut telemetry,
+    ) in q_wheels.iter_mut()
+    {
+        let Some(car_specs) = specs_assets.get(specs_handle) else {
+            continue;
+        };
+        let tires = &car_specs.tires;
+        let radius = car_specs.wheel_radius();
+        let speed = velocity.linvel.length();
+        let contact = wheel_contact(&rapier_context, entity, dt / substeps as f32);
+        let axle = wheel_spin_axis(transform.rotation, wheel.corner.is_left());
+        // In the air the slip is measured against the ground below, so the aids still see the
+        // wheel spinning up
+        let normal = contact.as_ref().map_or(Vec3::Y, |contact| contact.normal);
+        let forward = axle.cross(normal).normalize_or_zero();
+        let lateral = normal.cross(forward);
+
+        let longitudinal_speed = velocity.linvel.dot(forward);
+        let lateral_speed = velocity.linvel.dot(lateral);
+        let slip_speed = velocity.angvel.dot(axle) * radius - longitudinal_speed;
+        let reference_speed = longitudinal_speed.abs().max(MIN_SLIP_SPEED);
+        let slip_ratio = slip_speed / reference_speed;
+        let kinematic_slip_angle = (lateral_speed / reference_speed).atan();
+        telemetry.contact_speed = longitudinal_speed;
+        telemetry.slip_ratio = slip_ratio;
+        telemetry.slip_angle = kinematic_slip_angle;
+        telemetry.vertical_load = contact.as_ref().map_or(0., |contact| contact.load);
+
+        let Some(WheelContact {
+            point,
+            load,
+            surface,
+            ..
+        }) = contact
+        else {
+            tire.update(tires, &ambient, 0., speed, false, dt);
+            continue;
+        };
+        let surface = q_surfaces
+            .get(surface)
+            .copied()
+            .unwrap_or_default()
+            .properties();
+        // Lean of the wheel towards `lateral`, from its camber and the roll of the body
+        let inclination = -axle.dot(normal).asin();
+        let slip_angle = kinematic_slip_angle - tires.camber_thrust * inclination;
+
+        let friction_scale = tires.friction_scale(load) * tire.grip(tires) * surface.grip;
+        let mut longitudinal_force = tires.longitudinal.force(slip_ratio, load, friction_scale);
+        let mut lateral_force = -tires.lateral.force(slip_angle, load, friction_scale);
+
+        // Friction ellipse, the tire can't give its peak in both directions at once
+        let usage = (longitudinal_force / tires.longitudinal.peak(load, friction_scale))
+            .hypot(lateral_force / tires.lateral.peak(load, friction_scale));
+        if usage > 1. {
+            longitudinal_force /= usage;
+            lateral_force /= usage;
+        }
+
+        // A wheel held by its brake slows down with the car rather than on its own. The brake
+        // torque is the max force of the wheel joint motor, see `system_brakes`
+        let corner_mass = load / GRAVITY;
+        let brake_torque = joint
+            .data
+            .motor(JointAxis::AngX)
+            .map_or(0., |motor| motor.max_force);
+        // Forces that would bring the slip speeds to zero by the end of the frame, counting the
+        // drive torque already on the wheel
+        let drive_torque = force.torque.dot(axle);
+        let longitudinal_limit = if brake_torque >= longitudinal_force.abs() * radius {
+            HELD_SLIP_CANCEL_FRACTION * slip_speed * corner_mass / dt + drive_torque / radius
+        } else {
+            // The wheel spins around its local Y axis, and the brake slows it down too
+            let wheel_mass = mass.principal_inertia.y / radius.powi(2);
+            let spin = velocity.angvel.dot(axle);
+            SLIP_CANCEL_FRACTION * slip_speed * wheel_mass / dt
+                + (drive_torque - brake_torque * spin.signum()) / radius
+        };
+        let lateral_limit = -SLIP_CANCEL_FRACTION * lateral_speed * corner_mass / dt;
+        let longitudinal_force = clamp_towards_zero(longitudinal_force, longitudinal_limit);
+        let lateral_force = clamp_towards_zero(lateral_force, lateral_limit);
+        // Rolling resistance slows the wheel down, but never past standing still
+        let rolling_resistance = clamp_towards_zero(
+            -longitudinal_speed.signum() * surface.rolling_resistance * load,
+            -longitudinal_speed * corner_mass / dt,
+        );
+        let tire_force = forward * (longitudinal_force + rolling_resistance)
+            + lateral * lateral_force
+            + normal * surface.bump_force(point, load, speed);
+
+        // Power the tire dissipates sliding over the ground
+        let slip_power =
+            (longitudinal_force * slip_speed).abs() + (lateral_force * lateral_speed).abs();
+        tire.update(tires, &ambient, slip_power, speed, true, dt);
+
+        let center_of_mass = transform.transform_point(mass.local_center_of_mass);
+        *force += ExternalForce::at_point(tire_force, point, center_of_mass);
+    }
+}
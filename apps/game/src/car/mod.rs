@@ -4,24 +4,42 @@ use bevy_rapier3d::prelude::Collider;
 
 pub mod dynamics;
 pub mod objects;
+pub mod recovery;
+pub mod specs;
 
+pub use specs::*;
+
+/// Upright of the car `body`, jointed to it
 #[derive(Component)]
 pub struct Upright {
-    pub is_left: bool,
-    pub is_front: bool,
+    pub corner: Corner,
+    pub body: Entity,
 }
 
-// TODO: refactor to Wheel { is_front, is_left } unless its more performant as different Components
-// for querying
-#[derive(Component)]
-pub struct FrontWheel;
-
+/// Wheel of the car `body`, jointed to `upright`
 #[derive(Component)]
-pub struct RearWheel;
+pub struct Wheel {
+    pub corner: Corner,
+    pub upright: Entity,
+    pub body: Entity,
+}
 
 #[derive(Component)]
 pub struct Body;
 
+/// Car that a body, upright or wheel belongs to
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct CarId(pub u32);
+
+/// Who drives a car, held by its body
+#[derive(Component, Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Driver {
+    // Follows the controls of the local player
+    Player,
+    // Leaves the car parked on its brakes
+    Idle,
+}
+
 #[derive(Reflect, Resource, Default, InspectorOptions)]
 #[reflect(Resource, InspectorOptions)]
 pub struct Configuration {
@@ -32,36 +50,6 @@ pub struct Configuration {
     pub enable_physics: bool,
 }
 
-#[derive(Resource)]
-pub struct CarSpecs {
-    pub height: f32,
-    pub width: f32,
-    pub length: f32,
-    pub wheel_half_height: f32,
-    pub wheel_diameter: f32,
-    pub wheel_offset: f32,
-    pub mass: f32,
-    pub wheel_mass: f32,
-    pub upright_mass: f32,
-}
-
-impl Default for CarSpecs {
-    fn default() -> Self {
-        CarSpecs {
-            height: 0.95,
-            length: 5.5,
-            width: 2.,
-            wheel_half_height: 0.4,
-            wheel_diameter: 0.72,
-            wheel_offset: 0.2,
-            mass: 796.,
-            wheel_mass: 2.5,
-            upright_mass: 2.5,
-        }
-    }
-}
-
-#[derive(Resource, Default)]
 pub struct CarMatMeshColliderHandles {
     pub material: Handle<StandardMaterial>,
     pub wheel: Handle<Mesh>,
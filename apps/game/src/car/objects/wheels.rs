@@ -1,33 +1,38 @@
 use bevy::prelude::*;
 use bevy_rapier3d::prelude::*;
-use std::f32::consts::PI;
 
-use crate::car::dynamics::suspension::{make_front_upright_chasis_joint, make_upright_wheel_joint};
-use crate::car::dynamics::{UprightJoint, WheelJoint};
-use crate::car::{CarMatMeshColliderHandles, CarSpecs, FrontWheel, RearWheel, Upright};
+use crate::car::dynamics::suspension::{
+    make_front_upright_chasis_joint, make_upright_wheel_joint, upright_wheel_anchors,
+};
+use crate::car::dynamics::{telemetry::WheelTelemetry, tires::TireState, Drivetrain};
+use crate::car::{
+    CarId, CarMatMeshColliderHandles, CarSpecs, Corner, Upright, Wheel, WheelAlignment,
+};
 use crate::plugins::{GROUP_SURFACE, GROUP_WHEEL};
 
+/// Translation and rotation of the upright and the wheel of a corner, with the joints between
+/// them and the body at rest. The upright leans with the caster and the wheel with the camber and
+/// toe of `alignment`
 pub fn get_suspension_geometry(
     is_left: bool,
     upright_offset_relative: f32,
     wheel_offset_abs: f32,
     body_pos: Vec3,
     anchor: Vec3,
+    alignment: &WheelAlignment,
 ) -> ((Vec3, Quat), (Vec3, Quat)) {
     let upright_translation = Vec3::new(
         upright_offset_relative + body_pos.x + anchor.x,
         body_pos.y + anchor.y,
         body_pos.z + anchor.z,
     );
-    let upright_rotation = Quat::IDENTITY;
+    let upright_rotation = alignment.upright_rotation();
 
-    let (wheel_pos_x, wheel_rot_z) = if is_left {
-        (upright_translation.x - wheel_offset_abs, 0.5 * PI)
-    } else {
-        (upright_translation.x + wheel_offset_abs, PI * -0.5)
-    };
-    let wheel_translation = Vec3::new(wheel_pos_x, upright_translation.y, upright_translation.z);
-    let wheel_rotation = Quat::from_euler(EulerRot::XYZ, 0., 0., wheel_rot_z);
+    let wheel_rotation_on_upright = alignment.wheel_rotation(is_left);
+    let (upright_anchor, wheel_anchor) = upright_wheel_anchors(wheel_offset_abs, is_left);
+    let wheel_translation = upright_translation
+        + upright_rotation * (upright_anchor - wheel_rotation_on_upright * wheel_anchor);
+    let wheel_rotation = upright_rotation * wheel_rotation_on_upright;
 
     (
         (upright_translation, upright_rotation),
@@ -35,39 +40,78 @@ pub fn get_suspension_geometry(
     )
 }
 
+/// Mass properties of a wheel as a solid cylinder spinning around its local Y axis, rather than
+/// the ball of its collider. Driven wheels also carry the engine's inertia through `gear`
+pub fn wheel_mass_properties(
+    car_specs: &CarSpecs,
+    is_front: bool,
+    gear: i8,
+) -> ColliderMassProperties {
+    let axle_inertia = car_specs.wheel_inertia_in_gear(is_front, gear);
+    let radial_inertia = 0.5 * car_specs.wheel_inertia()
+        + car_specs.wheel_mass * car_specs.wheel_half_height.powi(2) / 12.;
+    ColliderMassProperties::MassProperties(MassProperties {
+        mass: car_specs.wheel_mass,
+        principal_inertia: Vec3::new(radial_inertia, axle_inertia, radial_inertia),
+        ..default()
+    })
+}
+
+/// Transforms of the upright and wheel of a corner relative to the car, with the suspension at
+/// rest
+pub fn corner_rest_transforms(car_specs: &CarSpecs, corner: Corner) -> (Transform, Transform) {
+    let ((upright_translation, upright_rotation), (wheel_translation, wheel_rotation)) =
+        get_suspension_geometry(
+            corner.is_left(),
+            0.,
+            car_specs.wheel_offset,
+            Vec3::ZERO,
+            car_specs.wheel_anchors[corner.index()],
+            car_specs.alignment.wheel(corner.is_front()),
+        );
+    (
+        Transform::from_translation(upright_translation).with_rotation(upright_rotation),
+        Transform::from_translation(wheel_translation).with_rotation(wheel_rotation),
+    )
+}
+
+/// Spawns the upright and wheel of a corner of the car `car_id`, jointed to its body
+#[allow(clippy::too_many_arguments)]
 pub fn spawn_wheel(
     car_transform: &Transform,
     car_handles: &CarMatMeshColliderHandles,
     commands: &mut Commands,
     car_specs: &CarSpecs,
+    specs_handle: &Handle<CarSpecs>,
+    car_id: CarId,
     body_entity: Entity,
-    anchor: Vec3,
-    wheel_num: usize,
+    corner: Corner,
 ) {
-    let is_front = wheel_num / 2 == 0;
-    let is_left = wheel_num % 2 == 0;
-
-    // Geometry
-    let ((upright_translation, upright_rotation), (wheel_translation, wheel_rotation)) =
-        get_suspension_geometry(is_left, 0., 0., car_transform.translation, anchor);
+    let (is_front, is_left) = (corner.is_front(), corner.is_left());
+    let anchor = car_specs.wheel_anchors[corner.index()];
+    let alignment = car_specs.alignment.wheel(is_front);
+    let (upright_rest, wheel_rest) = corner_rest_transforms(car_specs, corner);
 
     // upright
     let upright_entity = commands
         .spawn(PbrBundle {
             mesh: car_handles.upright.clone(),
             material: car_handles.material.clone(),
-            transform: Transform {
-                translation: upright_translation,
-                rotation: upright_rotation,
-                ..default()
-            },
+            transform: *car_transform * upright_rest,
             ..default()
         })
         .insert(RigidBody::Dynamic)
-        .insert(Name::new(format!("upright_{}", wheel_num)))
+        .insert(Name::new(format!("upright_{}", corner.index())))
         .insert(car_handles.upright_collider.clone())
         .insert(ColliderMassProperties::Mass(car_specs.upright_mass))
-        .insert(Upright { is_left, is_front })
+        .insert(ReadMassProperties::default())
+        .insert(Velocity::default())
+        .insert(ExternalForce::default())
+        .insert(Upright {
+            corner,
+            body: body_entity,
+        })
+        .insert((car_id, specs_handle.clone()))
         .id();
 
     // wheel
@@ -75,45 +119,64 @@ pub fn spawn_wheel(
         .spawn(PbrBundle {
             mesh: car_handles.wheel.clone(),
             material: car_handles.material.clone(),
-            transform: Transform {
-                translation: wheel_translation,
-                rotation: wheel_rotation,
-                ..default()
-            },
+            transform: *car_transform * wheel_rest,
             ..default()
         })
-        .insert(Name::new(format!("wheel_{}", wheel_num)))
+        .insert(Name::new(format!("wheel_{}", corner.index())))
         .insert(RigidBody::Dynamic)
         .insert(car_handles.wheel_collider.clone())
         // .insert(Ccd::enabled())
-        .insert(ColliderMassProperties::Mass(car_specs.wheel_mass))
+        .insert(wheel_mass_properties(
+            car_specs,
+            is_front,
+            Drivetrain::default().gear,
+        ))
+        .insert(ReadMassProperties::default())
+        .insert(Velocity::default())
+        .insert(ExternalForce::default())
         .insert(CollisionGroups::new(
             bevy_rapier3d::geometry::Group::from_bits_truncate(GROUP_WHEEL),
             bevy_rapier3d::geometry::Group::from_bits_truncate(GROUP_SURFACE),
         ))
         // .insert(Restitution::coefficient(0.5))
-        .insert(Friction::new(1.))
+        // Grip comes from the tire model, the collider only carries the normal load
+        .insert(Friction {
+            coefficient: 0.,
+            combine_rule: CoefficientCombineRule::Min,
+        })
+        .insert(TireState::new(&car_specs.tires))
+        .insert(WheelTelemetry::default())
+        .insert((car_id, specs_handle.clone()))
         .id();
 
-    if is_front {
-        commands.entity(wheel_entity).insert(FrontWheel);
-    } else {
-        commands.entity(wheel_entity).insert(RearWheel);
-    }
-
     // Wheel - Upright Joint
-    let wheel_joint = make_upright_wheel_joint(car_specs.wheel_offset, is_left);
+    let wheel_joint = make_upright_wheel_joint(
+        car_specs.wheel_offset,
+        is_left,
+        alignment.wheel_rotation(is_left),
+    );
 
     commands.entity(wheel_entity).insert((
         ImpulseJoint::new(upright_entity, wheel_joint),
-        WheelJoint { is_left, is_front },
+        Wheel {
+            corner,
+            upright: upright_entity,
+            body: body_entity,
+        },
     ));
 
     // Upright - Body Joint
-    let upright_joint = make_front_upright_chasis_joint(anchor, 0., [-1., 0.], !is_front);
+    let suspension = car_specs.suspension(corner);
+    let upright_joint = make_front_upright_chasis_joint(
+        anchor,
+        0.,
+        [-suspension.travel, 0.],
+        !is_front,
+        car_specs.steering.lock,
+        alignment.upright_rotation(),
+    );
 
-    commands.entity(upright_entity).insert((
-        ImpulseJoint::new(body_entity, upright_joint),
-        UprightJoint { is_left, is_front },
-    ));
+    commands
+        .entity(upright_entity)
+        .insert(ImpulseJoint::new(body_entity, upright_joint));
 }
@@ -0,0 +1,95 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::{RapierContext, Velocity};
+
+use crate::car::{
+    dynamics::Drivetrain, objects::wheels::corner_rest_transforms, Body, CarSpecs, Upright, Wheel,
+};
+use crate::plugins::controls::ControlsState;
+
+// Seconds a car has to be upside down or stuck before it is towed back on its own
+const RECOVERY_DELAY: f32 = 5.;
+// A car in gear on the throttle and off the brake, slower than this in m/s, is stuck
+const STUCK_SPEED: f32 = 0.5;
+const STUCK_ACCELERATOR: f32 = 0.5;
+const STUCK_BRAKE: f32 = 0.05;
+
+/// Where the car is towed back to: its pit box, or the last checkpoint it passed
+#[derive(Component, Clone, Copy)]
+pub struct RecoveryPoint(pub Transform);
+
+/// Seconds of simulation the body has been upside down or stuck
+#[derive(Component, Default)]
+pub struct RecoveryTimer(pub f32);
+
+/// Tows a car back to its recovery point when its driver asks for it, or once it has been upside
+/// down or stuck for a while. The body, uprights and wheels are put back at rest around the point,
+/// which leaves the joints between them unstrained, and stopped
+#[allow(clippy::type_complexity)]
+pub fn system_recovery(
+    rapier_context: Res<RapierContext>,
+    specs_assets: Res<Assets<CarSpecs>>,
+    mut q_body: Query<
+        (
+            Entity,
+            &Handle<CarSpecs>,
+            &ControlsState,
+            &Drivetrain,
+            &RecoveryPoint,
+            &mut RecoveryTimer,
+            &mut Transform,
+            &mut Velocity,
+        ),
+        With<Body>,
+    >,
+    mut q_parts: Query<
+        (
+            Option<&Upright>,
+            Option<&Wheel>,
+            &mut Transform,
+            &mut Velocity,
+        ),
+        (Or<(With<Upright>, With<Wheel>)>, Without<Body>),
+    >,
+) {
+    for (
+        body_entity,
+        specs_handle,
+        controls,
+        drivetrain,
+        point,
+        mut timer,
+        mut transform,
+        mut velocity,
+    ) in q_body.iter_mut()
+    {
+        let upside_down = transform.up().y < 0.;
+        let stuck = drivetrain.gear != 0
+            && controls.accelerator > STUCK_ACCELERATOR
+            && controls.brake < STUCK_BRAKE
+            && velocity.linvel.length() < STUCK_SPEED;
+        timer.0 = if upside_down || stuck {
+            timer.0 + rapier_context.integration_parameters.dt
+        } else {
+            0.
+        };
+        if !controls.tow_to_pits && timer.0 < RECOVERY_DELAY {
+            continue;
+        }
+        let Some(car_specs) = specs_assets.get(specs_handle) else {
+            continue;
+        };
+        timer.0 = 0.;
+        *transform = point.0;
+        *velocity = Velocity::default();
+        for (upright, wheel, mut part_transform, mut part_velocity) in q_parts.iter_mut() {
+            let (corner, is_wheel) = match (upright, wheel) {
+                (Some(upright), _) if upright.body == body_entity => (upright.corner, false),
+                (_, Some(wheel)) if wheel.body == body_entity => (wheel.corner, true),
+                _ => continue,
+            };
+            let (upright_rest, wheel_rest) = corner_rest_transforms(car_specs, corner);
+            *part_transform = point.0 * if is_wheel { wheel_rest } else { upright_rest };
+            *part_velocity = Velocity::default();
+        }
+    }
+}
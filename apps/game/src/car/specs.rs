@@ -0,0 +1,1008 @@
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+    utils::BoxedFuture,
+};
+use serde::Deserialize;
+use std::f32::consts::PI;
+use thiserror::Error;
+
+// In kPa
+const ATMOSPHERIC_PRESSURE: f32 = 101.325;
+// In K
+const ZERO_CELSIUS: f32 = 273.15;
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct SuspensionSpecs {
+    // Wheel travel from full droop to full bump, in meters
+    pub travel: f32,
+    // Spring rate in N/m
+    pub spring_rate: f32,
+    // Damping in N·s/m when the suspension compresses (bump) and extends (rebound)
+    pub bump_damping: f32,
+    pub rebound_damping: f32,
+    // Spring force at full droop, in N
+    pub preload: f32,
+    // Distance from full bump at which the bump stop engages, in meters
+    pub bump_stop_travel: f32,
+    // Bump stop rate in N/m, added to the spring rate once engaged
+    pub bump_stop_rate: f32,
+}
+
+impl Default for SuspensionSpecs {
+    fn default() -> Self {
+        SuspensionSpecs {
+            travel: 0.15,
+            spring_rate: 60000.,
+            bump_damping: 4000.,
+            rebound_damping: 6000.,
+            preload: 500.,
+            bump_stop_travel: 0.02,
+            bump_stop_rate: 300000.,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct AntiRollBarSpecs {
+    // Rates in N/m, per meter of difference between the left and right wheel travel
+    pub front: f32,
+    pub rear: f32,
+}
+
+impl Default for AntiRollBarSpecs {
+    fn default() -> Self {
+        AntiRollBarSpecs {
+            front: 40000.,
+            rear: 30000.,
+        }
+    }
+}
+
+impl AntiRollBarSpecs {
+    pub fn rate(&self, is_front: bool) -> f32 {
+        if is_front {
+            self.front
+        } else {
+            self.rear
+        }
+    }
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct SteeringSpecs {
+    // Steering wheel degrees per degree of front wheel angle
+    pub ratio: f32,
+    // Largest angle of either front wheel, in degrees
+    pub lock: f32,
+    // 1 for full Ackermann geometry, 0 for parallel steering, negative for anti-Ackermann
+    pub ackermann: f32,
+}
+
+impl Default for SteeringSpecs {
+    fn default() -> Self {
+        SteeringSpecs {
+            ratio: 10.,
+            lock: 45.,
+            ackermann: 1.,
+        }
+    }
+}
+
+impl SteeringSpecs {
+    /// Angle of a front wheel in degrees, positive to the right.
+    ///
+    /// The steering ratio gives the angle of a wheel at the center of the front axle, and with
+    /// full Ackermann the inner and outer wheels follow the circles around the turn center on
+    /// the line of the rear axle.
+    pub fn wheel_angle(
+        &self,
+        steering_wheel_degrees: f32,
+        is_left: bool,
+        wheelbase: f32,
+        track: f32,
+    ) -> f32 {
+        // Held at the lock, past which the turn center would fall between the front wheels and
+        // flip the inner one
+        let angle = (steering_wheel_degrees / self.ratio).clamp(-self.lock, self.lock);
+        if angle == 0. {
+            return 0.;
+        }
+        // Signed distance from the turn center to the center of the rear axle, positive when
+        // turning right
+        let radius = wheelbase / angle.to_radians().tan();
+        let offset = if is_left { track * 0.5 } else { -track * 0.5 };
+        let ackermann_angle = (wheelbase / (radius + offset)).atan().to_degrees();
+        (angle + self.ackermann * (ackermann_angle - angle)).clamp(-self.lock, self.lock)
+    }
+}
+
+#[derive(Clone, Copy, Default, Deserialize)]
+#[serde(default)]
+pub struct WheelAlignment {
+    // Degrees, negative tilts the top of the wheel towards the car
+    pub camber: f32,
+    // Degrees, positive (toe-in) points the front of the wheel towards the car
+    pub toe: f32,
+    // Degrees, positive tilts the top of the steering axis rearwards
+    pub caster: f32,
+}
+
+impl WheelAlignment {
+    /// Rotation of an upright relative to the body, tilting its steering axis by the caster
+    pub fn upright_rotation(&self) -> Quat {
+        Quat::from_rotation_x(self.caster.to_radians())
+    }
+
+    /// Rotation of a wheel relative to its upright. The wheel's local Y axis is its axle, which
+    /// points outwards on the left side and inwards on the right one
+    pub fn wheel_rotation(&self, is_left: bool) -> Quat {
+        let (toe, camber, axle) = if is_left {
+            (-self.toe, self.camber, 0.5 * PI)
+        } else {
+            (self.toe, -self.camber, -0.5 * PI)
+        };
+        Quat::from_rotation_y(toe.to_radians())
+            * Quat::from_rotation_z(camber.to_radians())
+            * Quat::from_rotation_z(axle)
+    }
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct AlignmentSpecs {
+    pub front: WheelAlignment,
+    pub rear: WheelAlignment,
+}
+
+impl Default for AlignmentSpecs {
+    fn default() -> Self {
+        AlignmentSpecs {
+            front: WheelAlignment {
+                camber: -3.,
+                toe: -0.1,
+                caster: 6.,
+            },
+            rear: WheelAlignment {
+                camber: -1.5,
+                toe: 0.2,
+                caster: 0.,
+            },
+        }
+    }
+}
+
+impl AlignmentSpecs {
+    pub fn wheel(&self, is_front: bool) -> &WheelAlignment {
+        if is_front {
+            &self.front
+        } else {
+            &self.rear
+        }
+    }
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct BrakeSpecs {
+    // Brake torque of the whole car at full pedal, in N·m
+    pub max_torque: f32,
+    // Fraction of the brake torque that goes to the front axle
+    pub bias: f32,
+    // Torque on each rear wheel when the handbrake is pulled, in N·m
+    pub handbrake_torque: f32,
+}
+
+impl Default for BrakeSpecs {
+    fn default() -> Self {
+        BrakeSpecs {
+            max_torque: 8000.,
+            bias: 0.6,
+            handbrake_torque: 1500.,
+        }
+    }
+}
+
+impl BrakeSpecs {
+    /// Brake torque on a single wheel, in N·m
+    pub fn wheel_torque(&self, is_front: bool, brake: f32, handbrake: bool) -> f32 {
+        if is_front {
+            brake * self.max_torque * self.bias * 0.5
+        } else {
+            let handbrake_torque = if handbrake { self.handbrake_torque } else { 0. };
+            brake * self.max_torque * (1. - self.bias) * 0.5 + handbrake_torque
+        }
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct DriverAidsSpecs {
+    // Slip ratio of the driven wheels above which each level of traction control cuts the
+    // engine torque, from the first level to the last
+    pub traction_control: Vec<f32>,
+    // Slip ratio below zero of a braked wheel above which each level of ABS releases its brake
+    pub abs: Vec<f32>,
+}
+
+impl Default for DriverAidsSpecs {
+    fn default() -> Self {
+        DriverAidsSpecs {
+            traction_control: vec![0.25, 0.15, 0.08],
+            abs: vec![0.2, 0.1],
+        }
+    }
+}
+
+impl DriverAidsSpecs {
+    /// Slip threshold of a traction control level, `None` when it's off
+    pub fn traction_control_slip(&self, level: usize) -> Option<f32> {
+        level
+            .checked_sub(1)
+            .and_then(|index| self.traction_control.get(index))
+            .copied()
+    }
+
+    /// Slip threshold of an ABS level, `None` when it's off
+    pub fn abs_slip(&self, level: usize) -> Option<f32> {
+        level
+            .checked_sub(1)
+            .and_then(|index| self.abs.get(index))
+            .copied()
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct EngineSpecs {
+    // Full throttle torque curve as (rpm, N·m) points, sorted by rpm
+    pub torque_curve: Vec<(f32, f32)>,
+    pub idle_rpm: f32,
+    // No torque is produced above this rpm
+    pub rev_limit: f32,
+    // Rotating inertia of the engine and flywheel, in kg·m²
+    pub inertia: f32,
+}
+
+impl Default for EngineSpecs {
+    fn default() -> Self {
+        EngineSpecs {
+            torque_curve: vec![
+                (1000., 200.),
+                (4000., 300.),
+                (8000., 380.),
+                (12000., 360.),
+                (15000., 300.),
+            ],
+            idle_rpm: 3000.,
+            rev_limit: 15000.,
+            inertia: 0.1,
+        }
+    }
+}
+
+impl EngineSpecs {
+    /// Full throttle torque at `rpm`, interpolated from the torque curve
+    pub fn torque(&self, rpm: f32) -> f32 {
+        let Some(&(first_rpm, first_torque)) = self.torque_curve.first() else {
+            return 0.;
+        };
+        if rpm <= first_rpm {
+            return first_torque;
+        }
+        for window in self.torque_curve.windows(2) {
+            let (rpm0, torque0) = window[0];
+            let (rpm1, torque1) = window[1];
+            if rpm <= rpm1 {
+                return torque0 + (torque1 - torque0) * (rpm - rpm0) / (rpm1 - rpm0);
+            }
+        }
+        self.torque_curve.last().map_or(0., |(_, torque)| *torque)
+    }
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct FuelSpecs {
+    // Size of the tank and fuel in it at the start, in liters
+    pub capacity: f32,
+    pub start_load: f32,
+    // Fuel burned per MJ of engine work, in liters
+    pub consumption: f32,
+    // Density of the fuel in kg/L
+    pub density: f32,
+}
+
+impl Default for FuelSpecs {
+    fn default() -> Self {
+        FuelSpecs {
+            capacity: 145.,
+            start_load: 60.,
+            consumption: 0.1,
+            density: 0.745,
+        }
+    }
+}
+
+impl FuelSpecs {
+    /// Mass of `fuel` liters, in kg
+    pub fn mass(&self, fuel: f32) -> f32 {
+        fuel * self.density
+    }
+}
+
+#[derive(Clone, Deserialize)]
+#[serde(default)]
+pub struct GearboxSpecs {
+    // Forward gear ratios, starting with first gear
+    pub ratios: Vec<f32>,
+    pub reverse_ratio: f32,
+    pub final_drive: f32,
+}
+
+impl Default for GearboxSpecs {
+    fn default() -> Self {
+        GearboxSpecs {
+            ratios: vec![3.2, 2.4, 1.95, 1.65, 1.42, 1.25],
+            reverse_ratio: 3.,
+            final_drive: 4.5,
+        }
+    }
+}
+
+impl GearboxSpecs {
+    /// Overall ratio from engine to wheels, negative in reverse and zero in neutral
+    pub fn ratio(&self, gear: i8) -> f32 {
+        match gear {
+            -1 => -self.reverse_ratio * self.final_drive,
+            0 => 0.,
+            _ => self
+                .ratios
+                .get(gear as usize - 1)
+                .map_or(0., |ratio| ratio * self.final_drive),
+        }
+    }
+
+    pub fn top_gear(&self) -> i8 {
+        self.ratios.len() as i8
+    }
+}
+
+#[derive(Clone, Copy, Deserialize)]
+pub enum DrivetrainLayout {
+    Rwd,
+    Fwd,
+    // `front_split` is the fraction of the torque sent to the front axle by the center
+    // differential
+    Awd { front_split: f32 },
+}
+
+impl DrivetrainLayout {
+    /// Fraction of the drive torque sent to the front and rear axles
+    pub fn axle_split(&self) -> (f32, f32) {
+        match self {
+            DrivetrainLayout::Rwd => (0., 1.),
+            DrivetrainLayout::Fwd => (1., 0.),
+            DrivetrainLayout::Awd { front_split } => (*front_split, 1. - front_split),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Deserialize)]
+pub enum Differential {
+    Open,
+    Locked,
+    // Clutch-type limited slip differential. Ramp angles are in degrees, the steeper the ramp
+    // the less the input torque locks the clutches
+    ClutchLsd {
+        // Locking torque with no input torque, in N·m
+        preload: f32,
+        power_ramp_angle: f32,
+        coast_ramp_angle: f32,
+        clutch_faces: u32,
+        clutch_friction: f32,
+    },
+}
+
+impl Differential {
+    /// Splits the axle torque between the left and right wheels.
+    ///
+    /// `equalizing_torque` is the torque that would have to move from the left to the right
+    /// wheel to bring both to the same speed, the differential transfers up to its locking
+    /// torque of it.
+    pub fn split(&self, torque: f32, equalizing_torque: f32) -> (f32, f32) {
+        let transfer = match self {
+            Differential::Open => 0.,
+            Differential::Locked => equalizing_torque,
+            Differential::ClutchLsd {
+                preload,
+                power_ramp_angle,
+                coast_ramp_angle,
+                clutch_faces,
+                clutch_friction,
+            } => {
+                let ramp_angle = if torque >= 0. {
+                    power_ramp_angle
+                } else {
+                    coast_ramp_angle
+                };
+                let locking_torque = preload
+                    + torque.abs() * *clutch_faces as f32 * clutch_friction
+                        / ramp_angle.to_radians().tan();
+                equalizing_torque.clamp(-locking_torque, locking_torque)
+            }
+        };
+        (torque * 0.5 - transfer, torque * 0.5 + transfer)
+    }
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct DrivetrainSpecs {
+    pub layout: DrivetrainLayout,
+    pub front_differential: Differential,
+    pub rear_differential: Differential,
+}
+
+impl Default for DrivetrainSpecs {
+    fn default() -> Self {
+        DrivetrainSpecs {
+            layout: DrivetrainLayout::Rwd,
+            front_differential: Differential::Open,
+            rear_differential: Differential::ClutchLsd {
+                preload: 50.,
+                power_ramp_angle: 45.,
+                coast_ramp_angle: 60.,
+                clutch_faces: 6,
+                clutch_friction: 0.1,
+            },
+        }
+    }
+}
+
+/// Coefficients of Pacejka's Magic Formula, `D·sin(C·atan(B·x − E·(B·x − atan(B·x))))` for a
+/// slip `x`, with `D` the friction coefficient that scales the normal load
+#[derive(Clone, Copy, Deserialize)]
+pub struct MagicFormula {
+    // Stiffness
+    pub b: f32,
+    // Shape
+    pub c: f32,
+    // Peak friction coefficient
+    pub d: f32,
+    // Curvature
+    pub e: f32,
+}
+
+impl MagicFormula {
+    /// Force for `slip` under `load`, in N. `friction_scale` scales the peak friction
+    pub fn force(&self, slip: f32, load: f32, friction_scale: f32) -> f32 {
+        let bx = self.b * slip;
+        self.d * friction_scale * load * (self.c * (bx - self.e * (bx - bx.atan())).atan()).sin()
+    }
+
+    pub fn peak(&self, load: f32, friction_scale: f32) -> f32 {
+        self.d * friction_scale * load
+    }
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct TireSpecs {
+    // Longitudinal force against slip ratio
+    pub longitudinal: MagicFormula,
+    // Lateral force against slip angle, in radians
+    pub lateral: MagicFormula,
+    // Normal load at which the coefficients above apply, in N
+    pub nominal_load: f32,
+    // Fraction of friction lost for each nominal load of extra load, so that grip grows slower
+    // than the load
+    pub load_sensitivity: f32,
+    // Slip angle equivalent to each radian of camber, the lean of the tire pushes it towards
+    // the side it leans to
+    pub camber_thrust: f32,
+    pub thermal: TireThermalSpecs,
+    // Gauge pressure in kPa when the tire is inflated, at the temperature it is inflated at
+    pub cold_pressure: f32,
+    pub inflation_temperature: f32,
+    // Gauge pressure in kPa at which the tire has its full grip
+    pub optimal_pressure: f32,
+    // Fraction of grip lost for each squared fraction of pressure away from the optimal one
+    pub pressure_sensitivity: f32,
+    // Fraction of the tread worn for each MJ of slip energy
+    pub wear_rate: f32,
+    // Fraction of grip left once the tread is fully worn
+    pub worn_grip: f32,
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct TireThermalSpecs {
+    // Heat capacities of the tread surface and the carcass, in J/K
+    pub surface_heat_capacity: f32,
+    pub core_heat_capacity: f32,
+    // Fraction of the slip power that heats the tread surface
+    pub slip_heating: f32,
+    // Heat transfer coefficients in W/K, from the surface to the core, from the surface to the
+    // track while touching it, and from the surface and the core to the air
+    pub surface_to_core: f32,
+    pub surface_to_track: f32,
+    pub surface_to_air: f32,
+    pub core_to_air: f32,
+    // Extra surface to air transfer for each m/s of speed, in W/K
+    pub speed_cooling: f32,
+    // Surface temperature in °C at which the tire has its full grip
+    pub optimal_temperature: f32,
+    // Distance in °C from the optimal temperature at which the grip drops to `cold_grip`
+    pub temperature_window: f32,
+    pub cold_grip: f32,
+}
+
+impl Default for TireThermalSpecs {
+    fn default() -> Self {
+        TireThermalSpecs {
+            surface_heat_capacity: 1500.,
+            core_heat_capacity: 8000.,
+            slip_heating: 0.5,
+            surface_to_core: 150.,
+            surface_to_track: 20.,
+            surface_to_air: 5.,
+            core_to_air: 5.,
+            speed_cooling: 0.5,
+            optimal_temperature: 90.,
+            temperature_window: 70.,
+            cold_grip: 0.85,
+        }
+    }
+}
+
+impl Default for TireSpecs {
+    fn default() -> Self {
+        TireSpecs {
+            longitudinal: MagicFormula {
+                b: 14.,
+                c: 1.65,
+                d: 1.6,
+                e: 0.3,
+            },
+            lateral: MagicFormula {
+                b: 18.,
+                c: 1.4,
+                d: 1.5,
+                e: 0.2,
+            },
+            nominal_load: 2000.,
+            load_sensitivity: 0.1,
+            camber_thrust: 0.1,
+            thermal: TireThermalSpecs::default(),
+            cold_pressure: 140.,
+            inflation_temperature: 20.,
+            optimal_pressure: 170.,
+            pressure_sensitivity: 1.,
+            wear_rate: 0.02,
+            worn_grip: 0.7,
+        }
+    }
+}
+
+impl TireSpecs {
+    /// Scale of the peak friction coefficients under `load`
+    pub fn friction_scale(&self, load: f32) -> f32 {
+        (1. - self.load_sensitivity * (load - self.nominal_load) / self.nominal_load).max(0.)
+    }
+
+    /// Scale of the peak friction coefficients from the surface temperature, pressure and wear of
+    /// a tire
+    pub fn condition_grip(&self, surface_temperature: f32, pressure: f32, wear: f32) -> f32 {
+        let thermal = &self.thermal;
+        let temperature_offset =
+            (surface_temperature - thermal.optimal_temperature) / thermal.temperature_window;
+        let temperature_grip = 1. - (1. - thermal.cold_grip) * temperature_offset.powi(2).min(1.);
+        let pressure_offset = (pressure - self.optimal_pressure) / self.optimal_pressure;
+        let pressure_grip = (1. - self.pressure_sensitivity * pressure_offset.powi(2)).max(0.);
+        let wear_grip = 1. - (1. - self.worn_grip) * wear;
+        temperature_grip * pressure_grip * wear_grip
+    }
+
+    /// Gauge pressure in kPa of the air in a tire at `temperature`, inflated to `cold_pressure`
+    /// at `inflation_temperature`
+    pub fn pressure(&self, temperature: f32) -> f32 {
+        (self.cold_pressure + ATMOSPHERIC_PRESSURE) * (temperature + ZERO_CELSIUS)
+            / (self.inflation_temperature + ZERO_CELSIUS)
+            - ATMOSPHERIC_PRESSURE
+    }
+}
+
+#[derive(Clone, Copy, Deserialize)]
+#[serde(default)]
+pub struct AeroSpecs {
+    // Air density in kg/m³
+    pub air_density: f32,
+    // Frontal area in m², the reference area of both coefficients
+    pub frontal_area: f32,
+    pub drag_coefficient: f32,
+    // Positive pushes the car down
+    pub downforce_coefficient: f32,
+    // Fraction of the aero forces acting at the front aero center
+    pub balance: f32,
+    // Points where the aero forces act, relative to the body
+    pub front_center: Vec3,
+    pub rear_center: Vec3,
+}
+
+impl Default for AeroSpecs {
+    fn default() -> Self {
+        AeroSpecs {
+            air_density: 1.225,
+            frontal_area: 1.5,
+            drag_coefficient: 0.9,
+            downforce_coefficient: 3.,
+            balance: 0.45,
+            front_center: Vec3::new(0., -0.3, -1.65),
+            rear_center: Vec3::new(0., -0.3, 2.2),
+        }
+    }
+}
+
+impl AeroSpecs {
+    /// Drag and downforce of the whole car at `speed`, in N
+    pub fn forces(&self, speed: f32) -> (f32, f32) {
+        let dynamic_pressure = 0.5 * self.air_density * speed * speed * self.frontal_area;
+        (
+            dynamic_pressure * self.drag_coefficient,
+            dynamic_pressure * self.downforce_coefficient,
+        )
+    }
+}
+
+/// Corner of the car where a wheel and its upright sit, in the order of the per-corner arrays of
+/// [`CarSpecs`]: FL, FR, RL, RR
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum Corner {
+    FrontLeft,
+    FrontRight,
+    RearLeft,
+    RearRight,
+}
+
+impl Corner {
+    pub const ALL: [Corner; 4] = [
+        Corner::FrontLeft,
+        Corner::FrontRight,
+        Corner::RearLeft,
+        Corner::RearRight,
+    ];
+
+    pub fn new(is_front: bool, is_left: bool) -> Self {
+        match (is_front, is_left) {
+            (true, true) => Corner::FrontLeft,
+            (true, false) => Corner::FrontRight,
+            (false, true) => Corner::RearLeft,
+            (false, false) => Corner::RearRight,
+        }
+    }
+
+    /// Index in the per-corner arrays
+    pub fn index(self) -> usize {
+        self as usize
+    }
+
+    pub fn is_front(self) -> bool {
+        matches!(self, Corner::FrontLeft | Corner::FrontRight)
+    }
+
+    pub fn is_left(self) -> bool {
+        matches!(self, Corner::FrontLeft | Corner::RearLeft)
+    }
+
+    /// Corner on the other side of the same axle
+    pub fn opposite(self) -> Self {
+        Corner::new(self.is_front(), !self.is_left())
+    }
+}
+
+/// Car definition, loaded from `assets/cars/*.ron`. Fields missing from the file or from any of
+/// its nested tables take their default values, except for the coefficients of a
+/// [`MagicFormula`], which are given in full. Every part of a car holds a handle to its specs.
+#[derive(Asset, TypePath, Clone, Deserialize)]
+#[serde(default)]
+pub struct CarSpecs {
+    pub height: f32,
+    pub width: f32,
+    pub length: f32,
+    pub wheel_half_height: f32,
+    pub wheel_diameter: f32,
+    pub wheel_offset: f32,
+    // Total mass of the car without fuel, including wheels and uprights
+    pub mass: f32,
+    pub wheel_mass: f32,
+    pub upright_mass: f32,
+    // Center of mass of the body, relative to the body's center
+    pub center_of_mass: Vec3,
+    // Principal moments of inertia of the body around its X (pitch), Y (yaw) and Z (roll) axes,
+    // in kg·m²
+    pub body_inertia: Vec3,
+    // Per corner, see `Corner`. Upright to body joint anchors, relative to the body
+    pub wheel_anchors: [Vec3; 4],
+    pub suspension: [SuspensionSpecs; 4],
+    pub anti_roll_bars: AntiRollBarSpecs,
+    pub steering: SteeringSpecs,
+    pub alignment: AlignmentSpecs,
+    pub brakes: BrakeSpecs,
+    pub driver_aids: DriverAidsSpecs,
+    pub engine: EngineSpecs,
+    pub fuel: FuelSpecs,
+    pub gearbox: GearboxSpecs,
+    pub drivetrain: DrivetrainSpecs,
+    pub tires: TireSpecs,
+    pub aero: AeroSpecs,
+}
+
+impl CarSpecs {
+    /// Mass of the body alone without fuel, the rest of the total goes to the wheels and uprights
+    pub fn body_mass(&self) -> f32 {
+        self.mass - 4. * (self.wheel_mass + self.upright_mass)
+    }
+
+    /// Moment of inertia of a wheel around its axle, as a solid cylinder
+    pub fn wheel_inertia(&self) -> f32 {
+        0.5 * self.wheel_mass * self.wheel_radius().powi(2)
+    }
+
+    /// Moment of inertia of a wheel around its axle with the engine in `gear`, driven wheels carry
+    /// their share of the engine inertia multiplied by the squared gear ratio
+    pub fn wheel_inertia_in_gear(&self, is_front: bool, gear: i8) -> f32 {
+        let (front_split, rear_split) = self.drivetrain.layout.axle_split();
+        let axle_split = if is_front { front_split } else { rear_split };
+        self.wheel_inertia()
+            + self.engine.inertia * self.gearbox.ratio(gear).powi(2) * axle_split * 0.5
+    }
+
+    pub fn wheel_radius(&self) -> f32 {
+        self.wheel_diameter * 0.5
+    }
+
+    /// Distance between the front and rear wheel anchors
+    pub fn wheelbase(&self) -> f32 {
+        self.wheel_anchors[Corner::RearLeft.index()].z
+            - self.wheel_anchors[Corner::FrontLeft.index()].z
+    }
+
+    /// Distance between the front left and right wheel anchors
+    pub fn front_track(&self) -> f32 {
+        self.wheel_anchors[Corner::FrontRight.index()].x
+            - self.wheel_anchors[Corner::FrontLeft.index()].x
+    }
+
+    pub fn suspension(&self, corner: Corner) -> &SuspensionSpecs {
+        &self.suspension[corner.index()]
+    }
+}
+
+impl Default for CarSpecs {
+    fn default() -> Self {
+        let height = 0.95;
+        let length = 5.5;
+        let width = 2.;
+        CarSpecs {
+            height,
+            length,
+            width,
+            wheel_half_height: 0.4,
+            wheel_diameter: 0.72,
+            wheel_offset: 0.2,
+            mass: 796.,
+            wheel_mass: 12.,
+            upright_mass: 8.,
+            center_of_mass: Vec3::new(0., -0.15, 0.2),
+            body_inertia: Vec3::new(2000., 2200., 320.),
+            wheel_anchors: [
+                Vec3::new(width * -0.5, height * -0.5, length * -0.3),
+                Vec3::new(width * 0.5, height * -0.5, length * -0.3),
+                Vec3::new(width * -0.5, height * -0.5, length * 0.4),
+                Vec3::new(width * 0.5, height * -0.5, length * 0.4),
+            ],
+            suspension: [SuspensionSpecs::default(); 4],
+            anti_roll_bars: AntiRollBarSpecs::default(),
+            steering: SteeringSpecs::default(),
+            alignment: AlignmentSpecs::default(),
+            brakes: BrakeSpecs::default(),
+            driver_aids: DriverAidsSpecs::default(),
+            engine: EngineSpecs::default(),
+            fuel: FuelSpecs::default(),
+            gearbox: GearboxSpecs::default(),
+            drivetrain: DrivetrainSpecs::default(),
+            tires: TireSpecs::default(),
+            aero: AeroSpecs::default(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct CarSpecsLoader;
+
+#[derive(Debug, Error)]
+pub enum CarSpecsLoaderError {
+    #[error("could not read car specs: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse car specs: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+    #[error("car mass of {mass} kg must be more than the {parts} kg of its wheels and uprights")]
+    NoBodyMass { mass: f32, parts: f32 },
+    #[error("steering ratio must be positive, not {0}")]
+    SteeringRatio(f32),
+}
+
+impl AssetLoader for CarSpecsLoader {
+    type Asset = CarSpecs;
+    type Settings = ();
+    type Error = CarSpecsLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let car_specs = ron::de::from_bytes::<CarSpecs>(&bytes)?;
+            if car_specs.body_mass() <= 0. {
+                return Err(CarSpecsLoaderError::NoBodyMass {
+                    mass: car_specs.mass,
+                    parts: car_specs.mass - car_specs.body_mass(),
+                });
+            }
+            let ratio = car_specs.steering.ratio;
+            if ratio <= 0. || ratio.is_nan() {
+                return Err(CarSpecsLoaderError::SteeringRatio(ratio));
+            }
+            Ok(car_specs)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ron"]
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_close(a: f32, b: f32) {
+        assert!((a - b).abs() < 1e-3, "{a} is not {b}");
+    }
+
+    const LSD: Differential = Differential::ClutchLsd {
+        preload: 50.,
+        power_ramp_angle: 45.,
+        coast_ramp_angle: 60.,
+        clutch_faces: 6,
+        clutch_friction: 0.1,
+    };
+
+    #[test]
+    fn open_differential_splits_evenly() {
+        assert_eq!(Differential::Open.split(100., 30.), (50., 50.));
+        assert_eq!(Differential::Open.split(-80., -30.), (-40., -40.));
+    }
+
+    #[test]
+    fn locked_differential_transfers_all_the_equalizing_torque() {
+        assert_eq!(Differential::Locked.split(100., 30.), (20., 80.));
+        assert_eq!(Differential::Locked.split(0., -25.), (25., -25.));
+    }
+
+    #[test]
+    fn clutch_lsd_transfers_up_to_its_locking_torque() {
+        // Under power the locking torque is 50 + 100·6·0.1 / tan(45°) = 110 N·m
+        let (left, right) = LSD.split(100., 30.);
+        assert_close(left, 20.);
+        assert_close(right, 80.);
+        let (left, right) = LSD.split(100., 200.);
+        assert_close(left, -60.);
+        assert_close(right, 160.);
+        // Coasting the steeper ramp locks less, 50 + 60 / tan(60°)
+        let locking_torque = 50. + 60. / 60_f32.to_radians().tan();
+        let (left, right) = LSD.split(-100., -200.);
+        assert_close(left, -50. + locking_torque);
+        assert_close(right, -50. - locking_torque);
+        // With no input torque only the preload locks it
+        let (left, right) = LSD.split(0., 200.);
+        assert_close(left, -50.);
+        assert_close(right, 50.);
+    }
+
+    #[test]
+    fn differentials_keep_the_axle_torque() {
+        for differential in [Differential::Open, Differential::Locked, LSD] {
+            for (torque, equalizing_torque) in [(100., 30.), (100., 500.), (-100., -500.)] {
+                let (left, right) = differential.split(torque, equalizing_torque);
+                assert_close(left + right, torque);
+            }
+        }
+    }
+
+    // Wheelbase and front track of the default car
+    const WHEELBASE: f32 = 3.85;
+    const TRACK: f32 = 2.;
+
+    fn wheel_angles(steering: &SteeringSpecs, steering_wheel_degrees: f32) -> (f32, f32) {
+        (
+            steering.wheel_angle(steering_wheel_degrees, true, WHEELBASE, TRACK),
+            steering.wheel_angle(steering_wheel_degrees, false, WHEELBASE, TRACK),
+        )
+    }
+
+    #[test]
+    fn centered_steering_wheel_points_the_wheels_straight() {
+        assert_eq!(wheel_angles(&SteeringSpecs::default(), 0.), (0., 0.));
+    }
+
+    #[test]
+    fn parallel_steering_follows_the_ratio() {
+        let steering = SteeringSpecs {
+            ratio: 10.,
+            lock: 45.,
+            ackermann: 0.,
+        };
+        let (left, right) = wheel_angles(&steering, 100.);
+        assert_close(left, 10.);
+        assert_close(right, 10.);
+    }
+
+    #[test]
+    fn ackermann_wheels_turn_around_a_point_on_the_rear_axle() {
+        let steering = SteeringSpecs::default();
+        // Turning right, the right wheel is on the inside
+        let (left, right) = wheel_angles(&steering, 100.);
+        assert!(left < 10. && right > 10.);
+        let left_radius = WHEELBASE / left.to_radians().tan();
+        let right_radius = WHEELBASE / right.to_radians().tan();
+        assert_close(left_radius - right_radius, TRACK);
+        // and turning left mirrors it
+        let (mirrored_left, mirrored_right) = wheel_angles(&steering, -100.);
+        assert_close(mirrored_left, -right);
+        assert_close(mirrored_right, -left);
+    }
+
+    #[test]
+    fn wheel_angles_stop_at_the_lock() {
+        let steering = SteeringSpecs {
+            lock: 30.,
+            ..default()
+        };
+        // The inner wheel reaches the lock, the outer one turns less
+        let (left, right) = wheel_angles(&steering, 450.);
+        assert!(left > 0. && left < 30.);
+        assert_close(right, 30.);
+        let (left, right) = wheel_angles(&steering, -450.);
+        assert_close(left, -30.);
+        assert!(right < 0. && right > -30.);
+        let parallel = SteeringSpecs {
+            ackermann: 0.,
+            ..steering
+        };
+        assert_eq!(wheel_angles(&parallel, 450.), (30., 30.));
+    }
+
+    #[test]
+    fn low_ratio_keeps_the_inner_wheel_turning_in() {
+        let steering = SteeringSpecs {
+            ratio: 5.,
+            ..default()
+        };
+        // Turning right, the outer left wheel turns less than the inner one at the lock
+        let (left, right) = wheel_angles(&steering, 450.);
+        assert!(left > 0. && left < 45.);
+        assert_close(right, 45.);
+        let (left, right) = wheel_angles(&steering, -450.);
+        assert_close(left, -45.);
+        assert!(right < 0. && right > -45.);
+    }
+}
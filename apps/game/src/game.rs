@@ -7,12 +7,9 @@ use bevy::{diagnostic::FrameTimeDiagnosticsPlugin, window::WindowResized};
 #[cfg(feature = "inspector")]
 use bevy_inspector_egui::quick::WorldInspectorPlugin;
 
-use bevy_rapier3d::{
-    prelude::{NoUserData, RapierPhysicsPlugin},
-    render::RapierDebugRenderPlugin,
-};
+use bevy_rapier3d::render::RapierDebugRenderPlugin;
 
-use crate::plugins::{CameraType, CarPlugin, ControlsPlugin, CubesPlugin, MainScenePlugin};
+use kazuki::KazukiPlugins;
 
 pub fn run() {
     let mut app = App::new();
@@ -30,7 +27,11 @@ pub fn run() {
                 }),
                 ..default()
             })
-            .set(AssetPlugin::default())
+            .set(AssetPlugin {
+                // Reload car specs and other assets when they change on disk
+                watch_for_changes_override: Some(true),
+                ..default()
+            })
             .set(TaskPoolPlugin {
                 task_pool_options: TaskPoolOptions {
                     compute: TaskPoolThreadAssignmentPolicy {
@@ -45,13 +46,7 @@ pub fn run() {
                 },
             }),
     )
-    .add_plugins(MainScenePlugin {
-        camera_type: CameraType::Follow,
-    })
-    .add_plugins(CarPlugin)
-    .add_plugins(ControlsPlugin)
-    .add_plugins(CubesPlugin)
-    .add_plugins(RapierPhysicsPlugin::<NoUserData>::default())
+    .add_plugins(KazukiPlugins)
     .add_plugins(FrameTimeDiagnosticsPlugin)
     .add_plugins(RapierDebugRenderPlugin::default())
     .add_systems(Update, on_resize_system);
@@ -0,0 +1,6 @@
+pub mod car;
+pub mod plugins;
+pub mod terrain;
+pub mod track;
+
+pub use plugins::KazukiPlugins;
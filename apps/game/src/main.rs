@@ -1,6 +1,4 @@
-pub mod car;
-pub mod game;
-pub mod plugins;
+mod game;
 
 pub fn main() {
     game::run();
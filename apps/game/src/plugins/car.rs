@@ -1,36 +1,244 @@
 use bevy::{
     app::{App, Plugin},
+    ecs::system::Command,
     prelude::*,
 };
 use bevy_rapier3d::prelude::*;
 
 use crate::car::{
-    dynamics::suspension::{system_rear_axle_motor, system_update_upright_steering},
+    dynamics::{
+        aero::system_aerodynamics,
+        aids::{system_driver_aids, DriverAids},
+        brakes::system_brakes,
+        engine::{system_driveline_inertia, system_engine, system_gearbox},
+        fuel::{body_mass_properties, system_fuel_mass, FuelTank},
+        suspension::{system_suspension_spring_damper, system_update_upright_steering},
+        system_reset_forces,
+        telemetry::system_wheel_telemetry,
+        tires::{system_tire_forces, AmbientConditions},
+        Drivetrain, DynamicsSet,
+    },
     objects::wheels::spawn_wheel,
-    Body, CarMatMeshColliderHandles, CarSpecs,
+    recovery::{system_recovery, RecoveryPoint, RecoveryTimer},
+    Body, CarId, CarMatMeshColliderHandles, CarSpecs, CarSpecsLoader, Corner, Driver, Upright,
+    Wheel,
 };
-use crate::plugins::{CarPlugin, GROUP_BODY, GROUP_SURFACE};
+use crate::plugins::{
+    controls::{ControlsSet, ControlsState},
+    CarPlugin, GROUP_BODY, GROUP_CHECKPOINT, GROUP_SURFACE,
+};
+use crate::track::timing::LapTimer;
 
 impl Plugin for CarPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<CarSpecs>()
-            .init_resource::<CarMatMeshColliderHandles>()
+        app.init_resource::<AmbientConditions>()
+            .init_resource::<NextCarId>()
+            .init_asset::<CarSpecs>()
+            .init_asset_loader::<CarSpecsLoader>()
+            .configure_sets(Update, DynamicsSet::ResetForces.before(DynamicsSet::Forces))
+            .configure_sets(Update, DynamicsSet::Forces.after(system_driver_controls))
             .add_systems(Startup, setup)
+            .add_systems(Update, (system_spawn_cars, system_check_assembly_mass))
+            .add_systems(
+                Update,
+                (
+                    system_update_upright_steering,
+                    system_driver_aids.before(system_brakes),
+                    system_suspension_spring_damper,
+                )
+                    .after(system_driver_controls),
+            )
+            // The brakes set the wheel motors that the tire forces read back, from the slips the
+            // tires found on the previous frame
+            .add_systems(
+                Update,
+                system_brakes
+                    .after(system_driver_controls)
+                    .after(system_wheel_telemetry)
+                    .before(DynamicsSet::Forces),
+            )
+            .add_systems(Update, system_driver_controls.after(ControlsSet))
+            .add_systems(
+                Update,
+                system_recovery
+                    .after(system_driver_controls)
+                    .before(DynamicsSet::ResetForces)
+                    .before(system_wheel_telemetry),
+            )
+            .add_systems(Update, system_driveline_inertia.after(system_gearbox))
+            .add_systems(Update, system_fuel_mass.after(system_engine))
+            .add_systems(Update, system_reset_forces.in_set(DynamicsSet::ResetForces))
+            .add_systems(Update, system_wheel_telemetry.before(DynamicsSet::Forces))
             .add_systems(
                 Update,
-                (system_update_upright_steering, system_rear_axle_motor),
+                (
+                    (system_gearbox, system_engine, system_tire_forces).chain(),
+                    system_aerodynamics,
+                )
+                    .in_set(DynamicsSet::Forces),
             );
     }
 }
 
-fn setup(
+const CAR_SPECS_PATH: &str = "cars/formula.ron";
+
+type CarPartFilter = Or<(With<Body>, With<Upright>, With<Wheel>)>;
+
+/// Spawns a car from its specs at `transform`, driven by `driver`. The car is built once its
+/// specs are loaded, and rebuilt there whenever they change on disk
+pub struct SpawnCar {
+    pub specs: Handle<CarSpecs>,
+    pub transform: Transform,
+    pub driver: Driver,
+}
+
+/// Where and how to build a car, kept for as long as the car exists so that it can be rebuilt
+#[derive(Component)]
+struct CarSpawner {
+    specs: Handle<CarSpecs>,
+    transform: Transform,
+    driver: Driver,
+}
+
+#[derive(Resource, Default)]
+struct NextCarId(u32);
+
+/// Body of a car whose assembly mass hasn't been checked yet
+#[derive(Component)]
+struct UncheckedMass;
+
+impl Command for SpawnCar {
+    fn apply(self, world: &mut World) {
+        let mut next_id = world.resource_mut::<NextCarId>();
+        let car_id = CarId(next_id.0);
+        next_id.0 += 1;
+        world.spawn((
+            car_id,
+            CarSpawner {
+                specs: self.specs,
+                transform: self.transform,
+                driver: self.driver,
+            },
+            Name::new(format!("Car {}", car_id.0)),
+        ));
+    }
+}
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.add(SpawnCar {
+        specs: asset_server.load(CAR_SPECS_PATH),
+        transform: Transform::from_xyz(-1., 2., -3.),
+        driver: Driver::Player,
+    });
+}
+
+/// Each car holds the controls of its driver, the player's come from the input
+fn system_driver_controls(
+    player_controls: Res<ControlsState>,
+    mut q_body: Query<(&Driver, &mut ControlsState), With<Body>>,
+) {
+    for (driver, mut controls) in q_body.iter_mut() {
+        *controls = match driver {
+            Driver::Player => player_controls.clone(),
+            Driver::Idle => ControlsState {
+                brake: 1.,
+                ..default()
+            },
+        };
+    }
+}
+
+/// Builds each car once its specs are loaded, and rebuilds it whenever they change on disk
+fn system_spawn_cars(
     mut commands: Commands,
+    mut events: EventReader<AssetEvent<CarSpecs>>,
+    specs_assets: Res<Assets<CarSpecs>>,
     mut meshes: ResMut<Assets<Mesh>>,
-    mut car_specs: ResMut<CarSpecs>,
     mut materials: ResMut<Assets<StandardMaterial>>,
-    mut car_handles: ResMut<CarMatMeshColliderHandles>,
+    q_spawners: Query<(&CarId, Ref<CarSpawner>)>,
+    q_car_parts: Query<(Entity, &CarId), CarPartFilter>,
+) {
+    let changed_specs = events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } => Some(*id),
+            _ => None,
+        })
+        .collect::<Vec<_>>();
+    for (car_id, spawner) in q_spawners.iter() {
+        if !spawner.is_added() && !changed_specs.contains(&spawner.specs.id()) {
+            continue;
+        }
+        let Some(car_specs) = specs_assets.get(&spawner.specs) else {
+            continue;
+        };
+        for (entity, part_car_id) in q_car_parts.iter() {
+            if part_car_id == car_id {
+                commands.entity(entity).despawn_recursive();
+            }
+        }
+        spawn_car(
+            &mut commands,
+            &mut meshes,
+            &mut materials,
+            car_specs,
+            &spawner,
+            *car_id,
+        );
+    }
+}
+
+/// Checks that the body, uprights and wheels of each new car add up to its declared total mass
+/// plus the fuel its body carries, once Rapier has computed their mass properties
+#[allow(clippy::type_complexity)]
+fn system_check_assembly_mass(
+    mut commands: Commands,
+    specs_assets: Res<Assets<CarSpecs>>,
+    q_body: Query<
+        (
+            Entity,
+            &CarId,
+            &Handle<CarSpecs>,
+            &ReadMassProperties,
+            &FuelTank,
+        ),
+        (With<Body>, With<UncheckedMass>),
+    >,
+    q_car_parts: Query<(&CarId, &ReadMassProperties), CarPartFilter>,
 ) {
-    let car_transform = Transform::from_xyz(-1., 2., -3.);
+    for (body_entity, car_id, specs_handle, body_mass, tank) in q_body.iter() {
+        let Some(car_specs) = specs_assets.get(specs_handle) else {
+            continue;
+        };
+        if body_mass.mass == 0. {
+            continue;
+        }
+        commands.entity(body_entity).remove::<UncheckedMass>();
+        let assembly_mass = q_car_parts
+            .iter()
+            .filter(|(part_car_id, _)| *part_car_id == car_id)
+            .map(|(_, mass)| mass.mass)
+            .sum::<f32>();
+        let expected_mass = car_specs.mass + tank.mass(&car_specs.fuel);
+        if (assembly_mass - expected_mass).abs() > 0.01 {
+            error!(
+                "Car {} assembly mass is {assembly_mass:.2} kg but the specs declare \
+                 {expected_mass:.2} kg with fuel",
+                car_id.0
+            );
+        }
+    }
+}
+
+fn spawn_car(
+    commands: &mut Commands,
+    meshes: &mut Assets<Mesh>,
+    materials: &mut Assets<StandardMaterial>,
+    car_specs: &CarSpecs,
+    spawner: &CarSpawner,
+    car_id: CarId,
+) {
+    let car_transform = spawner.transform;
     let body_mesh = meshes.add(Mesh::from(shape::Box {
         min_x: car_specs.width / -2.,
         max_x: car_specs.width / 2.,
@@ -45,19 +253,14 @@ fn setup(
         car_specs.length / 2.,
     );
 
-    let wheel_border_radius = 0.1;
     let wheel_mesh = meshes.add(Mesh::from(shape::Cylinder {
-        radius: car_specs.wheel_diameter / 2.,
+        radius: car_specs.wheel_radius(),
         height: car_specs.wheel_half_height,
         ..default()
     }));
-    let wheel_collider = Collider::round_cylinder(
-        car_specs.wheel_half_height - (wheel_border_radius * 2.),
-        car_specs.wheel_diameter / 2. - wheel_border_radius,
-        wheel_border_radius,
-    );
-    car_handles.wheel = wheel_mesh;
-    car_handles.wheel_collider = wheel_collider;
+    // The tire model needs the contact normal to go through the axle, which a ball guarantees.
+    // The contact points of a rounded cylinder wander and spin the wheels on their own
+    let wheel_collider = Collider::ball(car_specs.wheel_radius());
 
     let upright_mesh = meshes.add(Mesh::from(shape::Box {
         min_x: -0.1,
@@ -68,16 +271,17 @@ fn setup(
         max_z: 0.1,
     }));
     let upright_collider = Collider::cuboid(0.1, car_specs.wheel_half_height * 0.5, 0.1);
-    car_handles.upright = upright_mesh;
-    car_handles.upright_collider = upright_collider;
-
-    // material
-    car_handles.material = materials.add(Color::hsla(60.0, 0.0, 0.5, 0.5).into());
 
-    // calculate car mass
-    car_specs.mass -= 4. * (car_specs.wheel_mass + car_specs.upright_mass);
+    let car_handles = CarMatMeshColliderHandles {
+        material: materials.add(Color::hsla(60.0, 0.0, 0.5, 0.5).into()),
+        wheel: wheel_mesh,
+        upright: upright_mesh,
+        wheel_collider,
+        upright_collider,
+    };
 
     // body
+    let tank = FuelTank::new(&car_specs.fuel);
     let body_entity = commands
         .spawn(PbrBundle {
             mesh: body_mesh,
@@ -87,48 +291,45 @@ fn setup(
         })
         .insert(RigidBody::Dynamic)
         .insert(body_collider)
-        // TODO: check if this is the total mass and not added to the guessed one from the collider
-        // .insert(ColliderMassProperties::Mass(car_specs.mass))
+        .insert(Velocity::default())
+        .insert(ExternalForce::default())
+        .insert((ReadMassProperties::default(), UncheckedMass))
+        // The body collider is the only one attached to the body, so its mass properties are the
+        // body's
+        .insert(body_mass_properties(car_specs, tank.mass(&car_specs.fuel)))
         .insert(CollisionGroups::new(
             bevy_rapier3d::geometry::Group::from_bits_truncate(GROUP_BODY),
-            bevy_rapier3d::geometry::Group::from_bits_truncate(GROUP_BODY | GROUP_SURFACE),
+            bevy_rapier3d::geometry::Group::from_bits_truncate(
+                GROUP_BODY | GROUP_SURFACE | GROUP_CHECKPOINT,
+            ),
         ))
         .insert(Name::new("Body"))
         .insert(Body)
+        .insert(Drivetrain::default())
+        .insert(DriverAids::default())
+        .insert(tank)
+        .insert((
+            car_id,
+            spawner.specs.clone(),
+            spawner.driver,
+            ControlsState::default(),
+        ))
+        // Towed back to where it was spawned, until it passes a checkpoint
+        .insert((RecoveryPoint(car_transform), RecoveryTimer::default()))
+        .insert(LapTimer::default())
         .id();
 
     // wheels
-    let wheels_anchors = [
-        Vec3::new(
-            car_specs.width * -0.5,
-            car_specs.height * -0.5,
-            car_specs.length * -0.3,
-        ),
-        Vec3::new(
-            car_specs.width * 0.5,
-            car_specs.height * -0.5,
-            car_specs.length * -0.3,
-        ),
-        Vec3::new(
-            car_specs.width * -0.5,
-            car_specs.height * -0.5,
-            car_specs.length * 0.4,
-        ),
-        Vec3::new(
-            car_specs.width * 0.5,
-            car_specs.height * -0.5,
-            car_specs.length * 0.4,
-        ),
-    ];
-    for (i, anchor) in wheels_anchors.iter().enumerate() {
+    for corner in Corner::ALL {
         spawn_wheel(
             &car_transform,
             &car_handles,
-            &mut commands,
-            &car_specs,
+            commands,
+            car_specs,
+            &spawner.specs,
+            car_id,
             body_entity,
-            *anchor,
-            i,
+            corner,
         );
     }
 }
@@ -0,0 +1,158 @@
+use bevy::prelude::*;
+use bevy_inspector_egui::bevy_egui::{egui, EguiContexts};
+use leafwing_input_manager::{prelude::*, user_input::InputKind};
+
+use super::{
+    settings::{AxisCurve, InputSettings},
+    Action, ControlScheme, STEERING_WHEEL_LOCK,
+};
+
+/// Whether the input menu is shown, and the action waiting for the next button to bind to it
+#[derive(Resource, Default)]
+pub struct InputMenu {
+    pub open: bool,
+    rebinding: Option<Action>,
+}
+
+// Actions that can be bound to buttons from the menu
+const BUTTON_ACTIONS: [(Action, &str); 11] = [
+    (Action::SteerLeft, "Steer left"),
+    (Action::SteerRight, "Steer right"),
+    (Action::Accelerate, "Accelerate"),
+    (Action::Brake, "Brake"),
+    (Action::Handbrake, "Handbrake"),
+    (Action::ShiftUp, "Shift up"),
+    (Action::ShiftDown, "Shift down"),
+    (Action::CycleTractionControl, "Traction control"),
+    (Action::CycleAbs, "ABS"),
+    (Action::TowToPits, "Tow to pits"),
+    (Action::CycleControlScheme, "Next scheme"),
+];
+
+pub fn system_toggle_menu(query: Query<&ActionState<Action>>, mut menu: ResMut<InputMenu>) {
+    if menu.rebinding.is_none() && query.single().just_pressed(Action::ToggleMenu) {
+        menu.open = !menu.open;
+    }
+}
+
+/// Binds the next button pressed to the action waiting for one, Escape cancels
+pub fn system_rebind(
+    keys: Res<Input<KeyCode>>,
+    mouse_buttons: Res<Input<MouseButton>>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+    mut menu: ResMut<InputMenu>,
+    mut settings: ResMut<InputSettings>,
+) {
+    let Some(action) = menu.rebinding else {
+        return;
+    };
+    let input: UserInput = if keys.just_pressed(KeyCode::Escape) {
+        menu.rebinding = None;
+        return;
+    } else if let Some(key) = keys.get_just_pressed().next() {
+        (*key).into()
+    } else if let Some(button) = mouse_buttons.get_just_pressed().next() {
+        (*button).into()
+    } else if let Some(button) = gamepad_buttons.get_just_pressed().next() {
+        button.button_type.into()
+    } else {
+        return;
+    };
+    menu.rebinding = None;
+    let scheme = settings.scheme;
+    let device = settings.device_mut(scheme);
+    if !device.bindings.contains(&(action, input.clone())) {
+        device.bindings.push((action, input));
+    }
+}
+
+/// Edits a copy of the settings, so they only change, and get saved, when a value does
+pub fn system_input_menu(
+    mut contexts: EguiContexts,
+    mut menu: ResMut<InputMenu>,
+    mut settings: ResMut<InputSettings>,
+) {
+    if !menu.open {
+        return;
+    }
+    let mut edited = settings.clone();
+    let mut open = true;
+    let mut rebinding = menu.rebinding;
+    egui::Window::new("Controls")
+        .open(&mut open)
+        .show(contexts.ctx_mut(), |ui| {
+            egui::ComboBox::from_label("Scheme")
+                .selected_text(format!("{:?}", edited.scheme))
+                .show_ui(ui, |ui| {
+                    for scheme in ControlScheme::ALL {
+                        ui.selectable_value(&mut edited.scheme, scheme, format!("{scheme:?}"));
+                    }
+                });
+            let scheme = edited.scheme;
+            if scheme == ControlScheme::Keyboard {
+                let steering = &mut edited.keyboard_steering;
+                ui.add(egui::Slider::new(&mut steering.speed, 90.0..=1800.).text("steering °/s"));
+                ui.add(
+                    egui::Slider::new(&mut steering.return_speed, 90.0..=1800.).text("return °/s"),
+                );
+            }
+            let device = edited.device_mut(scheme);
+            ui.add(
+                egui::Slider::new(&mut device.steering_range, 45.0..=STEERING_WHEEL_LOCK)
+                    .text("steering range °"),
+            );
+            curve_ui(ui, "Steering", &mut device.steering);
+            curve_ui(ui, "Accelerator", &mut device.accelerator);
+            curve_ui(ui, "Brake", &mut device.brake);
+
+            ui.separator();
+            egui::Grid::new("bindings").show(ui, |ui| {
+                for (action, label) in BUTTON_ACTIONS {
+                    ui.label(label);
+                    let inputs: Vec<String> = device
+                        .bindings
+                        .iter()
+                        .filter(|(bound, _)| *bound == action)
+                        .map(|(_, input)| input_label(input))
+                        .collect();
+                    ui.label(inputs.join(", "));
+                    let add = if rebinding == Some(action) {
+                        "Press a button"
+                    } else {
+                        "Add"
+                    };
+                    if ui.button(add).clicked() {
+                        rebinding = Some(action);
+                    }
+                    if ui.button("Clear").clicked() {
+                        device.bindings.retain(|(bound, _)| *bound != action);
+                    }
+                    ui.end_row();
+                }
+            });
+            if ui.button("Reset to defaults").clicked() {
+                *device = InputSettings::default_device(scheme);
+            }
+        });
+    menu.open = open;
+    menu.rebinding = rebinding;
+    settings.set_if_neq(edited);
+}
+
+fn curve_ui(ui: &mut egui::Ui, label: &str, curve: &mut AxisCurve) {
+    ui.horizontal(|ui| {
+        ui.label(label);
+        ui.add(egui::Slider::new(&mut curve.deadzone, 0.0..=0.5).text("deadzone"));
+        ui.add(egui::Slider::new(&mut curve.gamma, 0.2..=5.).text("gamma"));
+        ui.checkbox(&mut curve.inverted, "inverted");
+    });
+}
+
+fn input_label(input: &UserInput) -> String {
+    match input {
+        UserInput::Single(InputKind::Keyboard(key)) => format!("{key:?}"),
+        UserInput::Single(InputKind::Mouse(button)) => format!("Mouse {button:?}"),
+        UserInput::Single(InputKind::GamepadButton(button)) => format!("Gamepad {button:?}"),
+        other => format!("{other:?}"),
+    }
+}
@@ -0,0 +1,369 @@
+use bevy::{
+    app::{App, Plugin},
+    input::InputSystem,
+    prelude::*,
+    window::PrimaryWindow,
+};
+use bevy_inspector_egui::bevy_egui::EguiPlugin;
+use leafwing_input_manager::{
+    axislike::DualAxisData, plugin::InputManagerSystem, prelude::*, systems::run_if_enabled,
+};
+use serde::{Deserialize, Serialize};
+
+use super::ControlsPlugin;
+
+pub mod menu;
+pub mod settings;
+
+use menu::{system_input_menu, system_rebind, system_toggle_menu, InputMenu};
+use settings::InputSettings;
+#[cfg(not(target_arch = "wasm32"))]
+use settings::INPUT_SETTINGS_PATH;
+
+/// Inputs of a driver. The resource holds the local player's, and the body of each car holds the
+/// ones of its driver
+#[derive(Default, Resource, Component, Clone)]
+pub struct ControlsState {
+    // Steering wheel is in the range [-450, 450]
+    pub steering_wheel_degrees: f32,
+    // Accelerator and brake are both in the range [0, 1]
+    pub accelerator: f32,
+    pub brake: f32,
+    pub handbrake: bool,
+    // Gear change requested this frame: 1 up, -1 down, 0 none
+    pub shift: i8,
+    // Change to the next driver aid level requested this frame
+    pub cycle_traction_control: bool,
+    pub cycle_abs: bool,
+    // Tow back to the pits requested this frame
+    pub tow_to_pits: bool,
+}
+
+/// Systems that write the `ControlsState` resource from the input. Anything that reads it runs
+/// after them, so that the presses of this frame are seen exactly once
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ControlsSet;
+
+#[derive(Actionlike, Clone, Debug, Copy, PartialEq, Eq, Reflect)]
+enum BoxMovement {
+    MousePosition,
+}
+
+/// Input devices the player drives with, each with its own settings. It can be changed at runtime
+/// from the menu, and the C key cycles through them
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ControlScheme {
+    // Mouse position steers and accelerates, left button brakes
+    #[default]
+    Mouse,
+    // Arrow keys or WASD
+    Keyboard,
+    // Left stick steers, triggers accelerate and brake
+    Gamepad,
+}
+
+impl ControlScheme {
+    pub const ALL: [ControlScheme; 3] = [
+        ControlScheme::Mouse,
+        ControlScheme::Keyboard,
+        ControlScheme::Gamepad,
+    ];
+
+    pub fn next(self) -> Self {
+        match self {
+            ControlScheme::Mouse => ControlScheme::Keyboard,
+            ControlScheme::Keyboard => ControlScheme::Gamepad,
+            ControlScheme::Gamepad => ControlScheme::Mouse,
+        }
+    }
+}
+
+// Range of the steering wheel in degrees, either side of the center
+pub const STEERING_WHEEL_LOCK: f32 = 450.;
+
+#[derive(Actionlike, PartialEq, Eq, Clone, Copy, Hash, Debug, Reflect, Serialize, Deserialize)]
+pub enum Action {
+    // Analog steering, -1 is full left and 1 full right
+    Steer,
+    SteerLeft,
+    SteerRight,
+    Accelerate,
+    Brake,
+    Handbrake,
+    ShiftUp,
+    ShiftDown,
+    CycleTractionControl,
+    CycleAbs,
+    TowToPits,
+    CycleControlScheme,
+    ToggleMenu,
+}
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        // There is no file system to keep the settings in on the web
+        #[cfg(not(target_arch = "wasm32"))]
+        let settings = InputSettings::load(INPUT_SETTINGS_PATH).unwrap_or_else(|e| {
+            warn!("{e}, using the default input settings");
+            InputSettings::default()
+        });
+        #[cfg(target_arch = "wasm32")]
+        let settings = InputSettings::default();
+        if !app.is_plugin_added::<EguiPlugin>() {
+            app.add_plugins(EguiPlugin);
+        }
+        app.init_resource::<ControlsState>()
+            .insert_resource(settings)
+            .init_resource::<InputMenu>()
+            .add_plugins(InputManagerPlugin::<Action>::default())
+            .add_plugins(InputManagerPlugin::<BoxMovement>::default())
+            .add_systems(Startup, setup)
+            .add_systems(
+                Update,
+                update_cursor_state_from_window
+                    .run_if(run_if_enabled::<BoxMovement>)
+                    .in_set(InputManagerSystem::ManualControl)
+                    .before(InputManagerSystem::ReleaseOnDisable)
+                    .after(InputManagerSystem::Tick)
+                    .after(InputManagerSystem::Update)
+                    .after(InputSystem),
+            )
+            .add_systems(
+                Update,
+                (
+                    (
+                        turn_steering_wheel.run_if(scheme_is(ControlScheme::Mouse)),
+                        steer_with_keys.run_if(scheme_is(ControlScheme::Keyboard)),
+                        steer_with_gamepad.run_if(scheme_is(ControlScheme::Gamepad)),
+                        update_pedals,
+                        update_gear_shift,
+                        update_driver_aids,
+                        update_tow_to_pits,
+                    )
+                        .run_if(menu_closed),
+                    release_controls.run_if(not(menu_closed)),
+                )
+                    .in_set(ControlsSet)
+                    .after(system_input_menu),
+            )
+            .add_systems(
+                Update,
+                (
+                    update_control_scheme.run_if(menu_closed),
+                    system_toggle_menu,
+                    system_rebind,
+                    system_input_menu,
+                    update_input_map.run_if(resource_changed::<InputSettings>()),
+                )
+                    .chain(),
+            );
+        #[cfg(not(target_arch = "wasm32"))]
+        app.add_systems(Update, save_input_settings.after(system_input_menu));
+    }
+}
+
+/// The mouse and keys drive the menu while it's open, rather than the car
+fn menu_closed(menu: Res<InputMenu>) -> bool {
+    !menu.open
+}
+
+/// Lets go of everything while the menu is open
+fn release_controls(mut controls: ResMut<ControlsState>) {
+    *controls = ControlsState::default();
+}
+
+fn scheme_is(scheme: ControlScheme) -> impl Fn(Res<InputSettings>) -> bool {
+    move |settings| settings.scheme == scheme
+}
+
+fn setup(
+    mut commands: Commands,
+    window: Query<Entity, With<PrimaryWindow>>,
+    settings: Res<InputSettings>,
+) {
+    commands.spawn(Camera2dBundle {
+        camera: Camera {
+            order: 0,
+            ..default()
+        },
+        ..default()
+    });
+
+    let entity = commands
+        .spawn(SpriteBundle {
+            transform: Transform::from_scale(Vec3::new(100., 100., 1.)),
+            visibility: Visibility::Hidden,
+            ..default()
+        })
+        .insert(InputManagerBundle::<BoxMovement>::default())
+        // Note: another entity will be driving this input
+        .id();
+
+    commands.entity(window.single()).insert(ActionStateDriver {
+        action: BoxMovement::MousePosition,
+        targets: entity.into(),
+    });
+
+    commands
+        .spawn(InputManagerBundle::<Action> {
+            input_map: settings.device(settings.scheme).input_map(),
+            ..default()
+        })
+        .insert(Name::new("Driver Input"));
+}
+
+fn update_control_scheme(query: Query<&ActionState<Action>>, mut settings: ResMut<InputSettings>) {
+    let action_state = query.single();
+    if action_state.just_pressed(Action::CycleControlScheme) {
+        settings.scheme = settings.scheme.next();
+    }
+}
+
+fn update_input_map(settings: Res<InputSettings>, mut query: Query<&mut InputMap<Action>>) {
+    for mut map in query.iter_mut() {
+        *map = settings.device(settings.scheme).input_map();
+    }
+}
+
+/// Writes the settings back to their file once they have changed and the menu is closed, so
+/// that dragging a slider doesn't write on every frame. They aren't written when first loaded
+#[cfg(not(target_arch = "wasm32"))]
+fn save_input_settings(
+    settings: Res<InputSettings>,
+    menu: Res<InputMenu>,
+    mut unsaved: Local<bool>,
+) {
+    if settings.is_changed() && !settings.is_added() {
+        *unsaved = true;
+    }
+    if !*unsaved || menu.open {
+        return;
+    }
+    *unsaved = false;
+    if let Err(e) = settings.save(INPUT_SETTINGS_PATH) {
+        warn!("{e}");
+    }
+}
+
+fn update_cursor_state_from_window(
+    window_query: Query<(&Window, &ActionStateDriver<BoxMovement>)>,
+    mut action_state_query: Query<&mut ActionState<BoxMovement>>,
+) {
+    // Update each actionstate with the mouse position from the window
+    // by using the referenced entities in ActionStateDriver and the stored action as
+    // a key into the action data
+    for (window, driver) in window_query.iter() {
+        for entity in driver.targets.iter() {
+            let mut action_state = action_state_query
+                .get_mut(*entity)
+                .expect("Entity does not exist, or does not have an `ActionState` component");
+
+            if let Some(val) = window.cursor_position() {
+                action_state.action_data_mut(driver.action).axis_pair =
+                    Some(DualAxisData::from_xy(val));
+            }
+        }
+    }
+}
+
+fn turn_steering_wheel(
+    settings: Res<InputSettings>,
+    window_query: Query<&Window>,
+    mut query: Query<&ActionState<BoxMovement>>,
+    mut controls: ResMut<ControlsState>,
+) {
+    let win = window_query.single();
+    let win_w = win.width();
+    let win_h = win.height();
+    let action_state = query.single_mut();
+    if let Some(x) = action_state.axis_pair(BoxMovement::MousePosition) {
+        let device = &settings.mouse;
+        controls.steering_wheel_degrees = device.steering_wheel_degrees(x.x() / win_w * 2. - 1.);
+        controls.accelerator = device.accelerator.pedal(1. - x.y() / win_h);
+    }
+}
+
+/// The steering keys turn the wheel at a steady speed, and it returns to the center when none is
+/// held. Turning against the current lock goes at least as fast as the return
+fn steer_with_keys(
+    time: Res<Time>,
+    settings: Res<InputSettings>,
+    query: Query<&ActionState<Action>>,
+    mut controls: ResMut<ControlsState>,
+) {
+    let action_state = query.single();
+    let direction = action_state.pressed(Action::SteerRight) as i8
+        - action_state.pressed(Action::SteerLeft) as i8;
+    let steering = &settings.keyboard_steering;
+    let range = settings
+        .keyboard
+        .steering_range
+        .clamp(0., STEERING_WHEEL_LOCK);
+    let angle = controls.steering_wheel_degrees;
+    let dt = time.delta_seconds();
+    controls.steering_wheel_degrees = match direction {
+        0 => angle - angle.signum() * (steering.return_speed * dt).min(angle.abs()),
+        _ => {
+            let direction = direction as f32;
+            let speed = if direction * angle < 0. {
+                steering.speed.max(steering.return_speed)
+            } else {
+                steering.speed
+            };
+            angle + direction * speed * dt
+        }
+    }
+    .clamp(-range, range);
+}
+
+fn steer_with_gamepad(
+    settings: Res<InputSettings>,
+    query: Query<&ActionState<Action>>,
+    mut controls: ResMut<ControlsState>,
+) {
+    let action_state = query.single();
+    controls.steering_wheel_degrees = settings
+        .gamepad
+        .steering_wheel_degrees(action_state.clamped_value(Action::Steer));
+}
+
+/// The accelerator follows the mouse in the mouse scheme, and its action in the others
+fn update_pedals(
+    settings: Res<InputSettings>,
+    query: Query<&ActionState<Action>>,
+    mut controls: ResMut<ControlsState>,
+) {
+    let action_state = query.single();
+    let device = settings.device(settings.scheme);
+    if settings.scheme != ControlScheme::Mouse {
+        controls.accelerator = device
+            .accelerator
+            .pedal(action_state.clamped_value(Action::Accelerate));
+    }
+    controls.brake = device
+        .brake
+        .pedal(action_state.clamped_value(Action::Brake));
+    controls.handbrake = action_state.pressed(Action::Handbrake);
+}
+
+fn update_gear_shift(query: Query<&ActionState<Action>>, mut controls: ResMut<ControlsState>) {
+    let action_state = query.single();
+    controls.shift = if action_state.just_pressed(Action::ShiftUp) {
+        1
+    } else if action_state.just_pressed(Action::ShiftDown) {
+        -1
+    } else {
+        0
+    };
+}
+
+fn update_driver_aids(query: Query<&ActionState<Action>>, mut controls: ResMut<ControlsState>) {
+    let action_state = query.single();
+    controls.cycle_traction_control = action_state.just_pressed(Action::CycleTractionControl);
+    controls.cycle_abs = action_state.just_pressed(Action::CycleAbs);
+}
+
+fn update_tow_to_pits(query: Query<&ActionState<Action>>, mut controls: ResMut<ControlsState>) {
+    let action_state = query.single();
+    controls.tow_to_pits = action_state.just_pressed(Action::TowToPits);
+}
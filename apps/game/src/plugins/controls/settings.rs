@@ -0,0 +1,252 @@
+use std::{fs, path::Path};
+
+use bevy::prelude::*;
+use leafwing_input_manager::prelude::*;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use super::{Action, ControlScheme, STEERING_WHEEL_LOCK};
+
+// Read at startup from the working directory, and written back once they have changed and the menu
+// is closed. Not used on the web
+pub const INPUT_SETTINGS_PATH: &str = "input_settings.ron";
+
+/// Bindings and response of each input device, and the scheme the player drives with
+#[derive(Resource, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct InputSettings {
+    pub scheme: ControlScheme,
+    pub mouse: DeviceSettings,
+    pub keyboard: DeviceSettings,
+    pub gamepad: DeviceSettings,
+    pub keyboard_steering: KeyboardSteering,
+}
+
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct DeviceSettings {
+    pub bindings: Vec<(Action, UserInput)>,
+    // Steering wheel degrees either side of the center at full input, up to the lock
+    pub steering_range: f32,
+    pub steering: AxisCurve,
+    pub accelerator: AxisCurve,
+    pub brake: AxisCurve,
+}
+
+/// Response of an analog input. The deadzone is the fraction of travel ignored at rest, and the
+/// rest is stretched over the full range and raised to the gamma: above 1 it is finer near rest
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AxisCurve {
+    pub deadzone: f32,
+    pub gamma: f32,
+    pub inverted: bool,
+}
+
+/// How fast the keyboard turns the steering wheel while a steering key is held, and returns it to
+/// the center once released, in degrees per second
+#[derive(Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct KeyboardSteering {
+    pub speed: f32,
+    pub return_speed: f32,
+}
+
+#[derive(Debug, Error)]
+pub enum InputSettingsError {
+    #[error("could not access input settings: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse input settings: {0}")]
+    Parse(#[from] ron::error::SpannedError),
+    #[error("could not write input settings: {0}")]
+    Write(#[from] ron::Error),
+}
+
+impl InputSettings {
+    /// Reads the settings at `path`, the defaults are used when there is no file yet
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, InputSettingsError> {
+        match fs::read_to_string(path) {
+            Ok(text) => Ok(ron::from_str(&text)?),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(InputSettings::default()),
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), InputSettingsError> {
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())?;
+        fs::write(path, text)?;
+        Ok(())
+    }
+
+    pub fn device(&self, scheme: ControlScheme) -> &DeviceSettings {
+        match scheme {
+            ControlScheme::Mouse => &self.mouse,
+            ControlScheme::Keyboard => &self.keyboard,
+            ControlScheme::Gamepad => &self.gamepad,
+        }
+    }
+
+    pub fn device_mut(&mut self, scheme: ControlScheme) -> &mut DeviceSettings {
+        match scheme {
+            ControlScheme::Mouse => &mut self.mouse,
+            ControlScheme::Keyboard => &mut self.keyboard,
+            ControlScheme::Gamepad => &mut self.gamepad,
+        }
+    }
+
+    pub fn default_device(scheme: ControlScheme) -> DeviceSettings {
+        match scheme {
+            ControlScheme::Mouse => DeviceSettings {
+                bindings: vec![
+                    (Action::Brake, MouseButton::Left.into()),
+                    (Action::Handbrake, KeyCode::Space.into()),
+                    (Action::ShiftUp, KeyCode::E.into()),
+                    (Action::ShiftDown, KeyCode::Q.into()),
+                    (Action::CycleTractionControl, KeyCode::T.into()),
+                    (Action::CycleAbs, KeyCode::B.into()),
+                    (Action::TowToPits, KeyCode::R.into()),
+                ],
+                ..default()
+            },
+            ControlScheme::Keyboard => DeviceSettings {
+                bindings: vec![
+                    (Action::SteerLeft, KeyCode::Left.into()),
+                    (Action::SteerLeft, KeyCode::A.into()),
+                    (Action::SteerRight, KeyCode::Right.into()),
+                    (Action::SteerRight, KeyCode::D.into()),
+                    (Action::Accelerate, KeyCode::Up.into()),
+                    (Action::Accelerate, KeyCode::W.into()),
+                    (Action::Brake, KeyCode::Down.into()),
+                    (Action::Brake, KeyCode::S.into()),
+                    (Action::Handbrake, KeyCode::Space.into()),
+                    (Action::ShiftUp, KeyCode::E.into()),
+                    (Action::ShiftDown, KeyCode::Q.into()),
+                    (Action::CycleTractionControl, KeyCode::T.into()),
+                    (Action::CycleAbs, KeyCode::B.into()),
+                    (Action::TowToPits, KeyCode::R.into()),
+                ],
+                ..default()
+            },
+            ControlScheme::Gamepad => DeviceSettings {
+                bindings: vec![
+                    (
+                        Action::Steer,
+                        SingleAxis::symmetric(GamepadAxisType::LeftStickX, 0.).into(),
+                    ),
+                    (Action::Accelerate, GamepadButtonType::RightTrigger2.into()),
+                    (Action::Brake, GamepadButtonType::LeftTrigger2.into()),
+                    (Action::Handbrake, GamepadButtonType::South.into()),
+                    (Action::ShiftUp, GamepadButtonType::RightTrigger.into()),
+                    (Action::ShiftDown, GamepadButtonType::LeftTrigger.into()),
+                    (
+                        Action::CycleTractionControl,
+                        GamepadButtonType::DPadUp.into(),
+                    ),
+                    (Action::CycleAbs, GamepadButtonType::DPadDown.into()),
+                    (Action::TowToPits, GamepadButtonType::North.into()),
+                    (Action::CycleControlScheme, GamepadButtonType::Select.into()),
+                    (Action::ToggleMenu, GamepadButtonType::Start.into()),
+                ],
+                steering: AxisCurve {
+                    deadzone: 0.08,
+                    gamma: 1.5,
+                    ..default()
+                },
+                accelerator: AxisCurve {
+                    deadzone: 0.05,
+                    ..default()
+                },
+                brake: AxisCurve {
+                    deadzone: 0.05,
+                    ..default()
+                },
+                ..default()
+            },
+        }
+    }
+}
+
+impl Default for InputSettings {
+    fn default() -> Self {
+        InputSettings {
+            scheme: ControlScheme::default(),
+            mouse: InputSettings::default_device(ControlScheme::Mouse),
+            keyboard: InputSettings::default_device(ControlScheme::Keyboard),
+            gamepad: InputSettings::default_device(ControlScheme::Gamepad),
+            keyboard_steering: KeyboardSteering::default(),
+        }
+    }
+}
+
+impl DeviceSettings {
+    /// Bindings of the device. Every scheme can switch to the next one with the C key and open
+    /// the menu with Escape
+    pub fn input_map(&self) -> InputMap<Action> {
+        let mut input_map = InputMap::default();
+        input_map
+            .insert(KeyCode::C, Action::CycleControlScheme)
+            .insert(KeyCode::Escape, Action::ToggleMenu);
+        for (action, input) in self.bindings.iter() {
+            input_map.insert(input.clone(), *action);
+        }
+        input_map
+    }
+
+    /// Steering wheel degrees for a steering input between -1 and 1
+    pub fn steering_wheel_degrees(&self, input: f32) -> f32 {
+        self.steering.axis(input) * self.steering_range.clamp(0., STEERING_WHEEL_LOCK)
+    }
+}
+
+impl Default for DeviceSettings {
+    fn default() -> Self {
+        DeviceSettings {
+            bindings: Vec::new(),
+            steering_range: STEERING_WHEEL_LOCK,
+            steering: AxisCurve::default(),
+            accelerator: AxisCurve::default(),
+            brake: AxisCurve::default(),
+        }
+    }
+}
+
+impl AxisCurve {
+    /// Shapes an input between 0 and 1
+    fn shape(&self, input: f32) -> f32 {
+        let deadzone = self.deadzone.clamp(0., 0.99);
+        ((input - deadzone) / (1. - deadzone))
+            .clamp(0., 1.)
+            .powf(self.gamma.max(0.01))
+    }
+
+    /// Response of a pedal to an input between 0 and 1, inverted pedals rest at 1
+    pub fn pedal(&self, input: f32) -> f32 {
+        let input = input.clamp(0., 1.);
+        self.shape(if self.inverted { 1. - input } else { input })
+    }
+
+    /// Response of an axis centered at 0 to an input between -1 and 1
+    pub fn axis(&self, input: f32) -> f32 {
+        let input = if self.inverted { -input } else { input };
+        input.signum() * self.shape(input.abs())
+    }
+}
+
+impl Default for AxisCurve {
+    fn default() -> Self {
+        AxisCurve {
+            deadzone: 0.,
+            gamma: 1.,
+            inverted: false,
+        }
+    }
+}
+
+impl Default for KeyboardSteering {
+    fn default() -> Self {
+        KeyboardSteering {
+            speed: 720.,
+            return_speed: 900.,
+        }
+    }
+}
@@ -4,6 +4,7 @@ use bevy_rapier3d::prelude::{Collider, CollisionGroups, RigidBody};
 use std::f32::consts::PI;
 
 use super::CubesPlugin;
+use crate::car::dynamics::surface::SurfaceMaterial;
 use crate::plugins::{GROUP_BODY, GROUP_SURFACE, GROUP_WHEEL};
 
 #[derive(Component)]
@@ -49,6 +50,7 @@ fn setup(
             .insert(CollisionGroups::new(
                 bevy_rapier3d::geometry::Group::from_bits_truncate(GROUP_SURFACE),
                 bevy_rapier3d::geometry::Group::from_bits_truncate(GROUP_BODY | GROUP_WHEEL),
-            ));
+            ))
+            .insert(SurfaceMaterial::Kerb);
     }
 }
@@ -8,8 +8,17 @@ use bevy_flycam::prelude::*;
 use bevy_rapier3d::prelude::*;
 
 use super::MainScenePlugin;
-use crate::car::Body;
-use crate::plugins::{CameraType, GROUP_BODY, GROUP_SURFACE, GROUP_WHEEL};
+use crate::car::{dynamics::surface::SurfaceMaterial, Body, Driver};
+use crate::plugins::{CameraType, Ground, GROUP_BODY, GROUP_SURFACE, GROUP_WHEEL};
+use crate::terrain::{TerrainLoader, TerrainSpecs};
+use crate::track::timing::LapTimer;
+
+/// The terrain the scene stands on, rebuilt whenever its files change on disk
+#[derive(Resource)]
+struct Terrain(Handle<TerrainSpecs>);
+
+#[derive(Component)]
+struct TerrainGround;
 
 #[derive(Component)]
 struct DebugText;
@@ -29,17 +38,36 @@ impl Plugin for MainScenePlugin {
                     .add_systems(Startup, setup_fly_camera);
             }
         };
+        match self.ground {
+            Ground::Plane => {
+                app.add_systems(Startup, setup_plane);
+            }
+            Ground::Terrain(path) => {
+                app.init_asset::<TerrainSpecs>()
+                    .init_asset_loader::<TerrainLoader>()
+                    .add_systems(
+                        Startup,
+                        move |mut commands: Commands, asset_server: Res<AssetServer>| {
+                            commands.insert_resource(Terrain(asset_server.load(path)));
+                        },
+                    )
+                    .add_systems(Update, system_spawn_terrain);
+            }
+        };
     }
 }
 
+/// Looks at the car driven by the player
 fn system_cam_follow(
     mut q_c: Query<&mut Transform, (With<Camera3d>, Without<Body>)>,
-    q_b: Query<&Transform, With<Body>>,
+    q_b: Query<(&Transform, &Driver), With<Body>>,
 ) {
-    if let Ok(mut cam_transform) = q_c.get_single_mut() {
-        if let Ok(body_transform) = q_b.get_single() {
-            cam_transform.look_at(body_transform.translation, Vec3::Y);
-        }
+    let Some((body_transform, _)) = q_b.iter().find(|(_, driver)| **driver == Driver::Player)
+    else {
+        return;
+    };
+    for mut cam_transform in q_c.iter_mut() {
+        cam_transform.look_at(body_transform.translation, Vec3::Y);
     }
 }
 
@@ -81,12 +109,11 @@ fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
     ));
 }
 
-fn setup_3d(
+fn setup_plane(
     mut commands: Commands,
     mut meshes: ResMut<Assets<Mesh>>,
     mut materials: ResMut<Assets<StandardMaterial>>,
 ) {
-    // plane
     commands
         .spawn(PbrBundle {
             mesh: meshes.add(shape::Plane::from_size(128.0).into()),
@@ -103,9 +130,54 @@ fn setup_3d(
                     bevy_rapier3d::geometry::Group::from_bits_truncate(GROUP_WHEEL | GROUP_BODY),
                 ))
                 .insert(Friction::new(1.))
+                .insert(SurfaceMaterial::Asphalt)
                 .insert(TransformBundle::from(Transform::from_xyz(0., -0.05, 0.)));
         });
+}
+
+fn system_spawn_terrain(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<TerrainSpecs>>,
+    terrain: Res<Terrain>,
+    specs_assets: Res<Assets<TerrainSpecs>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    q_ground: Query<Entity, With<TerrainGround>>,
+) {
+    let changed = events.read().any(|event| match event {
+        AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } => {
+            *id == terrain.0.id()
+        }
+        _ => false,
+    });
+    if !changed {
+        return;
+    }
+    let Some(specs) = specs_assets.get(&terrain.0) else {
+        return;
+    };
+    for entity in q_ground.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    commands
+        .spawn(PbrBundle {
+            mesh: meshes.add(specs.mesh()),
+            material: materials.add(Color::hsl(100., 0.3, 0.45).into()),
+            ..default()
+        })
+        .insert(RigidBody::Fixed)
+        .insert(specs.collider())
+        .insert(CollisionGroups::new(
+            bevy_rapier3d::geometry::Group::from_bits_truncate(GROUP_SURFACE),
+            bevy_rapier3d::geometry::Group::from_bits_truncate(GROUP_WHEEL | GROUP_BODY),
+        ))
+        .insert(Friction::new(1.))
+        .insert(specs.surface)
+        .insert(TerrainGround)
+        .insert(Name::new("Terrain"));
+}
 
+fn setup_3d(mut commands: Commands) {
     // ambient light
     commands.insert_resource(AmbientLight {
         color: Color::WHITE,
@@ -277,7 +349,33 @@ fn setup_fly_camera(mut commands: Commands) {
 fn text_update_system(
     diagnostics: Res<DiagnosticsStore>,
     mut query: Query<&mut Text, With<DebugText>>,
+    q_timer: Query<(&Driver, &LapTimer), With<Body>>,
 ) {
+    let lap = q_timer
+        .iter()
+        .find(|(driver, _)| **driver == Driver::Player)
+        .map(|(_, timer)| {
+            let splits = timer
+                .splits
+                .iter()
+                .map(|split| lap_time(Some(*split)))
+                .collect::<Vec<_>>()
+                .join(" ");
+            let sectors = timer
+                .last_sectors
+                .iter()
+                .map(|sector| lap_time(Some(*sector)))
+                .collect::<Vec<_>>()
+                .join(" ");
+            format!(
+                "\nLap {} {}  Last {}  Best {}\nSplits {splits}\nLast sectors {sectors}",
+                timer.laps + 1,
+                lap_time(timer.current),
+                lap_time(timer.last_lap),
+                lap_time(timer.best_lap),
+            )
+        })
+        .unwrap_or_default();
     let mut fps = 0.0;
     for mut text in &mut query {
         if let Some(fps_diagnostic) = diagnostics.get(FrameTimeDiagnosticsPlugin::FPS) {
@@ -285,7 +383,15 @@ fn text_update_system(
                 fps = fps_smoothed;
             }
         }
-        text.sections[1].value = format!("{fps:.2}");
+        text.sections[1].value = format!("{fps:.2}{lap}");
+    }
+}
+
+/// Seconds as minutes, seconds and milliseconds, or a dash for a time not set yet
+fn lap_time(seconds: Option<f32>) -> String {
+    match seconds {
+        Some(seconds) => format!("{}:{:06.3}", (seconds / 60.) as u32, seconds % 60.),
+        None => "-".to_string(),
     }
 }
 
@@ -1,20 +1,62 @@
+use bevy::app::{PluginGroup, PluginGroupBuilder};
+use bevy_rapier3d::prelude::TimestepMode;
+
 mod car;
 pub mod controls;
 mod cubes;
 mod main_scene;
+mod physics;
+mod track;
+
+pub use car::SpawnCar;
 
 pub enum CameraType {
     Follow,
     Fly,
 }
 
+/// What the scene stands on
+pub enum Ground {
+    // Flat 128 m plane
+    Plane,
+    // Heightmap terrain, from the path of its `.terrain.ron` asset
+    Terrain(&'static str),
+}
+
 pub struct CarPlugin;
 pub struct ControlsPlugin;
 pub struct CubesPlugin;
+/// Rapier, set up for the cars. By default the simulation follows the frame time, up to 1/60 s
+/// per frame split in 4 substeps. Headless tools can use a `Fixed` timestep instead
+pub struct PhysicsPlugin {
+    pub timestep_mode: TimestepMode,
+}
+pub struct TrackPlugin;
 pub struct MainScenePlugin {
     pub camera_type: CameraType,
+    pub ground: Ground,
+}
+
+/// The scene and its track, the car and its controls, and the physics they run on. The camera
+/// follows the car over hilly terrain, set a `MainScenePlugin` on the group to change them
+pub struct KazukiPlugins;
+
+impl PluginGroup for KazukiPlugins {
+    fn build(self) -> PluginGroupBuilder {
+        PluginGroupBuilder::start::<Self>()
+            .add(MainScenePlugin {
+                camera_type: CameraType::Follow,
+                ground: Ground::Terrain("terrains/hills.terrain.ron"),
+            })
+            .add(TrackPlugin)
+            .add(CarPlugin)
+            .add(ControlsPlugin)
+            .add(CubesPlugin)
+            .add(PhysicsPlugin::default())
+    }
 }
 
 pub const GROUP_SURFACE: u32 = 0b01;
 pub const GROUP_BODY: u32 = 0b10;
 pub const GROUP_WHEEL: u32 = 0b100;
+pub const GROUP_CHECKPOINT: u32 = 0b1000;
@@ -0,0 +1,39 @@
+use bevy::app::{App, Plugin};
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::*;
+
+use super::PhysicsPlugin;
+
+impl Default for PhysicsPlugin {
+    fn default() -> Self {
+        PhysicsPlugin {
+            timestep_mode: TimestepMode::Variable {
+                max_dt: 1. / 60.,
+                time_scale: 1.,
+                substeps: 4,
+            },
+        }
+    }
+}
+
+impl Plugin for PhysicsPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(RapierConfiguration {
+            timestep_mode: self.timestep_mode,
+            ..default()
+        })
+        .add_plugins(RapierPhysicsPlugin::<NoUserData>::default())
+        .add_systems(Startup, setup_solver);
+    }
+}
+
+/// The body is a few hundred times heavier than the uprights and wheels, which the joint solver
+/// only handles with more iterations than Rapier's defaults
+fn setup_solver(mut rapier_context: ResMut<RapierContext>) {
+    rapier_context
+        .integration_parameters
+        .max_velocity_iterations = 8;
+    rapier_context
+        .integration_parameters
+        .max_stabilization_iterations = 2;
+}
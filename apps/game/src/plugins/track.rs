@@ -0,0 +1,164 @@
+use bevy::{
+    app::{App, Plugin},
+    prelude::*,
+};
+use bevy_rapier3d::prelude::*;
+
+use super::TrackPlugin;
+use crate::car::dynamics::surface::SurfaceMaterial;
+use crate::plugins::{GROUP_BODY, GROUP_CHECKPOINT, GROUP_SURFACE, GROUP_WHEEL};
+use crate::track::{
+    mesh::track_geometry,
+    timing::{system_lap_timing, LapTimer, TimingLine},
+    TrackSpecs, TrackSpecsLoader,
+};
+
+const TRACK_PATH: &str = "tracks/circuit.track.ron";
+// Height of the timing line sensors above the road, and how far past its edges they reach
+const TIMING_LINE_HEIGHT: f32 = 4.;
+const TIMING_LINE_MARGIN: f32 = 2.;
+// Height above the road at which a car towed back to a timing line is dropped
+const RECOVERY_HEIGHT: f32 = 1.5;
+
+/// The track being driven, rebuilt whenever its file changes on disk
+#[derive(Resource)]
+struct Track(Handle<TrackSpecs>);
+
+#[derive(Component)]
+struct TrackSegment;
+
+type TrackPartFilter = Or<(With<TrackSegment>, With<TimingLine>)>;
+
+impl Plugin for TrackPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<TrackSpecs>()
+            .init_asset_loader::<TrackSpecsLoader>()
+            .add_systems(Startup, setup)
+            .add_systems(
+                Update,
+                (
+                    system_spawn_track,
+                    system_reset_lap_timers.before(system_lap_timing),
+                    system_lap_timing,
+                ),
+            );
+    }
+}
+
+fn setup(mut commands: Commands, asset_server: Res<AssetServer>) {
+    commands.insert_resource(Track(asset_server.load(TRACK_PATH)));
+}
+
+/// Builds the track once loaded, and rebuilds it whenever it changes on disk
+fn system_spawn_track(
+    mut commands: Commands,
+    mut events: EventReader<AssetEvent<TrackSpecs>>,
+    track: Res<Track>,
+    specs_assets: Res<Assets<TrackSpecs>>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    q_parts: Query<Entity, TrackPartFilter>,
+) {
+    if !track_changed(&mut events, &track) {
+        return;
+    }
+    let Some(specs) = specs_assets.get(&track.0) else {
+        return;
+    };
+    for entity in q_parts.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    for (i, segment) in track_geometry(specs).into_iter().enumerate() {
+        commands
+            .spawn(PbrBundle {
+                mesh: meshes.add(segment.mesh),
+                material: materials.add(surface_color(segment.surface).into()),
+                ..default()
+            })
+            .insert(RigidBody::Fixed)
+            .insert(segment.collider)
+            .insert(CollisionGroups::new(
+                bevy_rapier3d::geometry::Group::from_bits_truncate(GROUP_SURFACE),
+                bevy_rapier3d::geometry::Group::from_bits_truncate(GROUP_WHEEL | GROUP_BODY),
+            ))
+            .insert(Friction::new(1.))
+            .insert(segment.surface)
+            .insert(TrackSegment)
+            .insert(Name::new(format!("Track segment {i}")));
+    }
+    if specs.segment_count() == 0 {
+        if !specs.timing_lines.is_empty() {
+            warn!("Timing lines need a track with at least two points");
+        }
+        return;
+    }
+    let mut points = specs.timing_lines.clone();
+    points.retain(|&point| {
+        let on_track = point < specs.points.len();
+        if !on_track {
+            warn!("Timing line at point {point} is past the end of the track");
+        }
+        on_track
+    });
+    let count = points.len();
+    for (index, point) in points.into_iter().enumerate() {
+        let (position, direction, width) = specs.at_point(point);
+        let line_transform =
+            Transform::from_translation(position + Vec3::Y * TIMING_LINE_HEIGHT / 2.)
+                .looking_to(direction, Vec3::Y);
+        commands
+            .spawn(TransformBundle::from(line_transform))
+            .insert(Collider::cuboid(
+                width / 2. + TIMING_LINE_MARGIN,
+                TIMING_LINE_HEIGHT / 2.,
+                0.1,
+            ))
+            .insert(Sensor)
+            .insert(ActiveEvents::COLLISION_EVENTS)
+            .insert(CollisionGroups::new(
+                bevy_rapier3d::geometry::Group::from_bits_truncate(GROUP_CHECKPOINT),
+                bevy_rapier3d::geometry::Group::from_bits_truncate(GROUP_BODY),
+            ))
+            .insert(TimingLine {
+                index,
+                count,
+                recovery: Transform::from_translation(position + Vec3::Y * RECOVERY_HEIGHT)
+                    .looking_to(direction, Vec3::Y),
+            })
+            .insert(Name::new(format!("Timing line {index}")));
+    }
+}
+
+/// The lap timers of the cars start over whenever the track is built, as their lines may have
+/// moved or gone
+fn system_reset_lap_timers(
+    mut events: EventReader<AssetEvent<TrackSpecs>>,
+    track: Res<Track>,
+    mut q_timers: Query<&mut LapTimer>,
+) {
+    if !track_changed(&mut events, &track) {
+        return;
+    }
+    for mut timer in q_timers.iter_mut() {
+        *timer = LapTimer::default();
+    }
+}
+
+fn track_changed(events: &mut EventReader<AssetEvent<TrackSpecs>>, track: &Track) -> bool {
+    events.read().any(|event| match event {
+        AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } => {
+            *id == track.0.id()
+        }
+        _ => false,
+    })
+}
+
+fn surface_color(surface: SurfaceMaterial) -> Color {
+    match surface {
+        SurfaceMaterial::Asphalt => Color::hsl(0., 0., 0.3),
+        SurfaceMaterial::Kerb => Color::hsl(0., 0.7, 0.5),
+        SurfaceMaterial::Grass => Color::hsl(110., 0.5, 0.4),
+        SurfaceMaterial::Gravel => Color::hsl(40., 0.4, 0.6),
+        SurfaceMaterial::WetAsphalt => Color::hsl(220., 0.15, 0.22),
+    }
+}
@@ -0,0 +1,199 @@
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext, ReadAssetBytesError},
+    prelude::*,
+    render::{
+        mesh::Indices,
+        render_resource::{PrimitiveTopology, TextureFormat},
+        texture::{CompressedImageFormats, ImageSampler, ImageType, TextureError},
+    },
+    utils::BoxedFuture,
+};
+use bevy_rapier3d::prelude::Collider;
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::car::dynamics::surface::SurfaceMaterial;
+
+/// Ground built from a grayscale heightmap, centered on the origin. Each pixel is a vertex, the
+/// image rows running along +Z and its columns along +X
+#[derive(Asset, TypePath, Clone)]
+pub struct TerrainSpecs {
+    // Meters covered along x and z
+    pub size: Vec2,
+    pub surface: SurfaceMaterial,
+    // Elevation of each pixel in meters, row after row
+    pub heights: Vec<f32>,
+    pub rows: usize,
+    pub columns: usize,
+}
+
+/// The `.terrain.ron` file, pointing at the heightmap image and scaling it
+#[derive(Deserialize)]
+struct TerrainFile {
+    heightmap: String,
+    size: Vec2,
+    // Elevation of white pixels above black ones
+    height: f32,
+    // Elevation of black pixels
+    #[serde(default)]
+    base: f32,
+    #[serde(default)]
+    surface: SurfaceMaterial,
+}
+
+impl TerrainSpecs {
+    fn height(&self, row: usize, column: usize) -> f32 {
+        self.heights[row * self.columns + column]
+    }
+
+    /// Position of the vertex of a pixel
+    fn vertex(&self, row: usize, column: usize) -> Vec3 {
+        Vec3::new(
+            (column as f32 / (self.columns - 1) as f32 - 0.5) * self.size.x,
+            self.height(row, column),
+            (row as f32 / (self.rows - 1) as f32 - 0.5) * self.size.y,
+        )
+    }
+
+    /// Render mesh, split into the same triangles as the collider
+    pub fn mesh(&self) -> Mesh {
+        let (rows, columns) = (self.rows, self.columns);
+        let mut positions = Vec::with_capacity(rows * columns);
+        let mut normals = Vec::with_capacity(rows * columns);
+        let mut uvs = Vec::with_capacity(rows * columns);
+        for row in 0..rows {
+            for column in 0..columns {
+                positions.push(self.vertex(row, column));
+                // Slope from the neighbouring pixels, or the pixel itself at the edges
+                let left = self.vertex(row, column.saturating_sub(1));
+                let right = self.vertex(row, (column + 1).min(columns - 1));
+                let back = self.vertex(row.saturating_sub(1), column);
+                let front = self.vertex((row + 1).min(rows - 1), column);
+                normals.push((front - back).cross(right - left).normalize_or_zero());
+                uvs.push(Vec2::new(
+                    column as f32 / (columns - 1) as f32,
+                    row as f32 / (rows - 1) as f32,
+                ));
+            }
+        }
+
+        let index = |row: usize, column: usize| (row * columns + column) as u32;
+        let indices = (0..rows - 1)
+            .flat_map(|row| (0..columns - 1).map(move |column| (row, column)))
+            .flat_map(|(row, column)| {
+                let (v00, v01) = (index(row, column), index(row, column + 1));
+                let (v10, v11) = (index(row + 1, column), index(row + 1, column + 1));
+                [v00, v10, v01, v10, v11, v01]
+            })
+            .collect();
+
+        let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+        mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+        mesh.set_indices(Some(Indices::U32(indices)));
+        mesh
+    }
+
+    pub fn collider(&self) -> Collider {
+        // Rapier takes the heights column after column
+        let heights = (0..self.columns)
+            .flat_map(|column| (0..self.rows).map(move |row| (row, column)))
+            .map(|(row, column)| self.height(row, column))
+            .collect();
+        Collider::heightfield(
+            heights,
+            self.rows,
+            self.columns,
+            Vec3::new(self.size.x, 1., self.size.y),
+        )
+    }
+}
+
+/// Brightness of each pixel of a grayscale heightmap between 0 and 1, row after row
+fn heightmap_samples(image: &Image) -> Result<Vec<f32>, TerrainLoaderError> {
+    let data = &image.data;
+    Ok(match image.texture_descriptor.format {
+        // 16 bit grayscale
+        TextureFormat::R16Uint | TextureFormat::R16Unorm => data
+            .chunks_exact(2)
+            .map(|bytes| u16::from_ne_bytes([bytes[0], bytes[1]]) as f32 / u16::MAX as f32)
+            .collect(),
+        TextureFormat::R8Unorm => data.iter().map(|v| *v as f32 / 255.).collect(),
+        // 8 bit grayscale is expanded to RGBA
+        TextureFormat::Rgba8Unorm | TextureFormat::Rgba8UnormSrgb => data
+            .chunks_exact(4)
+            .map(|pixel| pixel[0] as f32 / 255.)
+            .collect(),
+        format => return Err(TerrainLoaderError::UnsupportedFormat(format)),
+    })
+}
+
+#[derive(Default)]
+pub struct TerrainLoader;
+
+#[derive(Debug, Error)]
+pub enum TerrainLoaderError {
+    #[error("could not read terrain: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse terrain: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+    #[error("could not read heightmap: {0}")]
+    ReadHeightmap(#[from] ReadAssetBytesError),
+    #[error("could not decode heightmap: {0}")]
+    DecodeHeightmap(#[from] TextureError),
+    #[error("heightmap format {0:?} is not supported, use 8 or 16 bit grayscale")]
+    UnsupportedFormat(TextureFormat),
+    #[error("heightmap must be at least 2 by 2 pixels")]
+    TooSmall,
+}
+
+impl AssetLoader for TerrainLoader {
+    type Asset = TerrainSpecs;
+    type Settings = ();
+    type Error = TerrainLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let file = ron::de::from_bytes::<TerrainFile>(&bytes)?;
+
+            // Read as a dependency, so that editing the image reloads the terrain
+            let image_bytes = load_context.read_asset_bytes(&file.heightmap).await?;
+            let extension = file.heightmap.rsplit('.').next().unwrap_or_default();
+            let image = Image::from_buffer(
+                &image_bytes,
+                ImageType::Extension(extension),
+                CompressedImageFormats::NONE,
+                false,
+                ImageSampler::Default,
+            )?;
+            let (rows, columns) = (image.height() as usize, image.width() as usize);
+            if rows < 2 || columns < 2 {
+                return Err(TerrainLoaderError::TooSmall);
+            }
+            let heights = heightmap_samples(&image)?
+                .into_iter()
+                .map(|sample| file.base + sample * file.height)
+                .collect();
+
+            Ok(TerrainSpecs {
+                size: file.size,
+                surface: file.surface,
+                heights,
+                rows,
+                columns,
+            })
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["terrain.ron"]
+    }
+}
@@ -0,0 +1,79 @@
+use bevy::{
+    prelude::*,
+    render::{mesh::Indices, render_resource::PrimitiveTopology},
+};
+use bevy_rapier3d::prelude::Collider;
+
+use crate::car::dynamics::surface::SurfaceMaterial;
+use crate::track::TrackSpecs;
+
+// Samples used to measure the length of a segment before splitting it into cross sections
+const LENGTH_SAMPLES: usize = 16;
+
+/// Road surface of a segment of the track, rendered by `mesh` and collided with through
+/// `collider`
+pub struct TrackSegmentGeometry {
+    pub surface: SurfaceMaterial,
+    pub mesh: Mesh,
+    pub collider: Collider,
+}
+
+/// Geometry of each segment of the track. Neighbouring segments share their end cross sections,
+/// so the road has no gaps between them
+pub fn track_geometry(specs: &TrackSpecs) -> Vec<TrackSegmentGeometry> {
+    let resolution = specs.resolution.max(0.1);
+    (0..specs.segment_count())
+        .map(|segment| {
+            let length = (1..=LENGTH_SAMPLES)
+                .map(|i| {
+                    let t0 = (i - 1) as f32 / LENGTH_SAMPLES as f32;
+                    let t1 = i as f32 / LENGTH_SAMPLES as f32;
+                    specs
+                        .centerline(segment, t0)
+                        .0
+                        .distance(specs.centerline(segment, t1).0)
+                })
+                .sum::<f32>();
+            let sections = ((length / resolution).ceil() as usize).max(1);
+
+            let mut positions = Vec::with_capacity(2 * (sections + 1));
+            let mut normals = Vec::with_capacity(2 * (sections + 1));
+            let mut uvs = Vec::with_capacity(2 * (sections + 1));
+            for i in 0..=sections {
+                let t = i as f32 / sections as f32;
+                let (center, tangent) = specs.centerline(segment, t);
+                let (width, banking) = specs.cross_section(segment, t);
+                let forward = tangent.normalize_or_zero();
+                let right = Quat::from_axis_angle(forward, banking)
+                    * forward.cross(Vec3::Y).normalize_or_zero();
+                let normal = right.cross(forward).normalize_or_zero();
+                let v = t * length / width;
+                positions.push(center - right * width / 2.);
+                positions.push(center + right * width / 2.);
+                normals.extend([normal, normal]);
+                uvs.extend([Vec2::new(0., v), Vec2::new(1., v)]);
+            }
+
+            // Two triangles between each pair of cross sections, counterclockwise from above
+            let triangles = (0..sections as u32)
+                .flat_map(|i| {
+                    let (left, right) = (2 * i, 2 * i + 1);
+                    let (next_left, next_right) = (left + 2, right + 2);
+                    [[left, right, next_right], [left, next_right, next_left]]
+                })
+                .collect::<Vec<_>>();
+
+            let mut mesh = Mesh::new(PrimitiveTopology::TriangleList);
+            mesh.insert_attribute(Mesh::ATTRIBUTE_POSITION, positions.clone());
+            mesh.insert_attribute(Mesh::ATTRIBUTE_NORMAL, normals);
+            mesh.insert_attribute(Mesh::ATTRIBUTE_UV_0, uvs);
+            mesh.set_indices(Some(Indices::U32(triangles.concat())));
+
+            TrackSegmentGeometry {
+                surface: specs.surface(segment),
+                mesh,
+                collider: Collider::trimesh(positions, triangles),
+            }
+        })
+        .collect()
+}
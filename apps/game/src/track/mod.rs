@@ -0,0 +1,143 @@
+use bevy::{
+    asset::{io::Reader, AssetLoader, AsyncReadExt, LoadContext},
+    prelude::*,
+    utils::BoxedFuture,
+};
+use serde::Deserialize;
+use thiserror::Error;
+
+use crate::car::dynamics::surface::SurfaceMaterial;
+
+pub mod mesh;
+pub mod timing;
+
+/// A road following a Catmull-Rom spline through its control points. Width, banking and
+/// elevation are set at each point and blend along the segment to the next one
+#[derive(Asset, TypePath, Clone, Deserialize)]
+pub struct TrackSpecs {
+    // Joins the last control point back to the first, for a circuit
+    pub closed: bool,
+    // Longest distance between the cross sections of the road, in meters
+    pub resolution: f32,
+    pub points: Vec<TrackPoint>,
+    // Control points with a timing line across the road. The first is the start/finish line,
+    // the others end each sector of the lap
+    #[serde(default)]
+    pub timing_lines: Vec<usize>,
+}
+
+#[derive(Clone, Copy, Deserialize)]
+pub struct TrackPoint {
+    // Point of the centerline, y is its elevation
+    pub position: Vec3,
+    // Meters from edge to edge
+    pub width: f32,
+    // Degrees, positive raises the left edge of the road
+    #[serde(default)]
+    pub banking: f32,
+    // Surface of the segment from this point to the next
+    #[serde(default)]
+    pub surface: SurfaceMaterial,
+}
+
+impl TrackSpecs {
+    /// Number of segments between control points
+    pub fn segment_count(&self) -> usize {
+        match self.points.len() {
+            0 | 1 => 0,
+            n if self.closed => n,
+            n => n - 1,
+        }
+    }
+
+    /// Control point `i`, wrapping around a closed track and held at the ends of an open one
+    fn point(&self, i: isize) -> &TrackPoint {
+        let n = self.points.len() as isize;
+        let i = if self.closed {
+            i.rem_euclid(n)
+        } else {
+            i.clamp(0, n - 1)
+        };
+        &self.points[i as usize]
+    }
+
+    /// Point of the centerline of segment `segment` at `t` between 0 and 1, and its tangent
+    pub fn centerline(&self, segment: usize, t: f32) -> (Vec3, Vec3) {
+        let i = segment as isize;
+        let [p0, p1, p2, p3] = [i - 1, i, i + 1, i + 2].map(|i| self.point(i).position);
+        let a = 2. * p1;
+        let b = p2 - p0;
+        let c = 2. * p0 - 5. * p1 + 4. * p2 - p3;
+        let d = -p0 + 3. * p1 - 3. * p2 + p3;
+        let position = 0.5 * (a + b * t + c * t * t + d * t * t * t);
+        let tangent = 0.5 * (b + 2. * c * t + 3. * d * t * t);
+        (position, tangent)
+    }
+
+    /// Width and banking in radians of segment `segment` at `t` between 0 and 1
+    pub fn cross_section(&self, segment: usize, t: f32) -> (f32, f32) {
+        let i = segment as isize;
+        let (start, end) = (self.point(i), self.point(i + 1));
+        let t = t * t * (3. - 2. * t);
+        (
+            start.width + (end.width - start.width) * t,
+            (start.banking + (end.banking - start.banking) * t).to_radians(),
+        )
+    }
+
+    /// Point of the centerline at control point `point`, the direction of travel there and the
+    /// width of the road. The track must have at least one segment
+    pub fn at_point(&self, point: usize) -> (Vec3, Vec3, f32) {
+        // The last point of an open track ends the last segment rather than starting one
+        let (segment, t) = if point < self.segment_count() {
+            (point, 0.)
+        } else {
+            (point - 1, 1.)
+        };
+        let (position, tangent) = self.centerline(segment, t);
+        (
+            position,
+            tangent.normalize_or_zero(),
+            self.cross_section(segment, t).0,
+        )
+    }
+
+    pub fn surface(&self, segment: usize) -> SurfaceMaterial {
+        self.point(segment as isize).surface
+    }
+}
+
+#[derive(Default)]
+pub struct TrackSpecsLoader;
+
+#[derive(Debug, Error)]
+pub enum TrackSpecsLoaderError {
+    #[error("could not read track: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("could not parse track: {0}")]
+    Ron(#[from] ron::error::SpannedError),
+}
+
+impl AssetLoader for TrackSpecsLoader {
+    type Asset = TrackSpecs;
+    type Settings = ();
+    type Error = TrackSpecsLoaderError;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a (),
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            Ok(ron::de::from_bytes::<TrackSpecs>(&bytes)?)
+        })
+    }
+
+    // Car specs take plain .ron files
+    fn extensions(&self) -> &[&str] {
+        &["track.ron"]
+    }
+}
@@ -0,0 +1,180 @@
+use bevy::prelude::*;
+use bevy_rapier3d::prelude::{CollisionEvent, RapierContext};
+
+use crate::car::{recovery::RecoveryPoint, Body};
+
+/// Sensor across the road that times the cars going through it. Line 0 is the start/finish line,
+/// and the others end each sector in turn
+#[derive(Component)]
+pub struct TimingLine {
+    pub index: usize,
+    pub count: usize,
+    // Where a car that went through the line is towed back to
+    pub recovery: Transform,
+}
+
+/// Lap times of a car, in seconds of simulation. Its first lap starts when it first crosses the
+/// start/finish line, and lines crossed out of order, cutting the track or driving it backwards,
+/// are ignored
+#[derive(Component, Default)]
+pub struct LapTimer {
+    // Laps completed
+    pub laps: u32,
+    // Time into the current lap, none before the first start
+    pub current: Option<f32>,
+    pub last_lap: Option<f32>,
+    pub best_lap: Option<f32>,
+    // Time into the current lap at which each sector ended
+    pub splits: Vec<f32>,
+    // Time of each sector of the last lap
+    pub last_sectors: Vec<f32>,
+    next_line: usize,
+}
+
+impl LapTimer {
+    /// Times the car crossing `line`, and returns whether it was the next line of the lap
+    fn cross(&mut self, line: &TimingLine) -> bool {
+        if line.index != self.next_line {
+            return false;
+        }
+        self.next_line = (line.index + 1) % line.count;
+        let Some(time) = self.current else {
+            self.current = Some(0.);
+            return true;
+        };
+        self.splits.push(time);
+        if line.index != 0 {
+            return true;
+        }
+        let mut sector_start = 0.;
+        self.last_sectors = self
+            .splits
+            .drain(..)
+            .map(|split| {
+                let sector = split - sector_start;
+                sector_start = split;
+                sector
+            })
+            .collect();
+        self.laps += 1;
+        self.last_lap = Some(time);
+        self.best_lap = Some(self.best_lap.map_or(time, |best| best.min(time)));
+        self.current = Some(0.);
+        true
+    }
+}
+
+/// Runs the lap clocks and times the bodies going through the timing lines. A car going through
+/// the next line of its lap is towed back there from then on
+pub fn system_lap_timing(
+    rapier_context: Res<RapierContext>,
+    mut events: EventReader<CollisionEvent>,
+    q_lines: Query<&TimingLine>,
+    mut q_body: Query<(&mut LapTimer, &mut RecoveryPoint), With<Body>>,
+) {
+    let dt = rapier_context.integration_parameters.dt;
+    for (mut timer, _) in q_body.iter_mut() {
+        if let Some(current) = timer.current.as_mut() {
+            *current += dt;
+        }
+    }
+    for event in events.read() {
+        let CollisionEvent::Started(a, b, _) = event else {
+            continue;
+        };
+        let (line, body) = match (q_lines.get(*a), q_lines.get(*b)) {
+            (Ok(line), _) => (line, *b),
+            (_, Ok(line)) => (line, *a),
+            _ => continue,
+        };
+        let Ok((mut timer, mut recovery)) = q_body.get_mut(body) else {
+            continue;
+        };
+        if timer.cross(line) {
+            recovery.0 = line.recovery;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(count: usize) -> Vec<TimingLine> {
+        (0..count)
+            .map(|index| TimingLine {
+                index,
+                count,
+                recovery: Transform::IDENTITY,
+            })
+            .collect()
+    }
+
+    fn drive(timer: &mut LapTimer, seconds: f32) {
+        if let Some(current) = timer.current.as_mut() {
+            *current += seconds;
+        }
+    }
+
+    #[test]
+    fn lap_starts_at_the_start_finish_line() {
+        let lines = lines(3);
+        let mut timer = LapTimer::default();
+        assert!(!timer.cross(&lines[1]));
+        assert_eq!(timer.current, None);
+        assert!(timer.cross(&lines[0]));
+        assert_eq!(timer.current, Some(0.));
+        assert_eq!(timer.laps, 0);
+    }
+
+    #[test]
+    fn laps_in_order_record_splits_and_sectors() {
+        let lines = lines(3);
+        let mut timer = LapTimer::default();
+        timer.cross(&lines[0]);
+        drive(&mut timer, 10.);
+        assert!(timer.cross(&lines[1]));
+        drive(&mut timer, 15.);
+        assert!(timer.cross(&lines[2]));
+        assert_eq!(timer.splits, vec![10., 25.]);
+        drive(&mut timer, 20.);
+        assert!(timer.cross(&lines[0]));
+        assert_eq!(timer.laps, 1);
+        assert_eq!(timer.last_lap, Some(45.));
+        assert_eq!(timer.best_lap, Some(45.));
+        assert_eq!(timer.last_sectors, vec![10., 15., 20.]);
+        assert!(timer.splits.is_empty());
+        assert_eq!(timer.current, Some(0.));
+    }
+
+    #[test]
+    fn skipped_lines_are_ignored_until_the_next_one() {
+        let lines = lines(3);
+        let mut timer = LapTimer::default();
+        timer.cross(&lines[0]);
+        drive(&mut timer, 10.);
+        // Cutting past line 1 and back over the start/finish line doesn't count
+        assert!(!timer.cross(&lines[2]));
+        assert!(!timer.cross(&lines[0]));
+        assert_eq!(timer.laps, 0);
+        assert!(timer.splits.is_empty());
+        assert_eq!(timer.current, Some(10.));
+        assert!(timer.cross(&lines[1]));
+        assert_eq!(timer.splits, vec![10.]);
+    }
+
+    #[test]
+    fn best_lap_keeps_the_fastest() {
+        let lines = lines(1);
+        let mut timer = LapTimer::default();
+        timer.cross(&lines[0]);
+        for lap in [50., 40., 45.] {
+            drive(&mut timer, lap);
+            assert!(timer.cross(&lines[0]));
+            assert_eq!(timer.last_lap, Some(lap));
+            assert_eq!(timer.last_sectors, vec![lap]);
+        }
+        assert_eq!(timer.laps, 3);
+        assert_eq!(timer.best_lap, Some(40.));
+    }
+}